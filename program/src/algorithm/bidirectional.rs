@@ -0,0 +1,332 @@
+use crate::{
+    algorithm::{
+        dijkstra::DijkstraDirection,
+        weight::{Decimetres, Metric, Weight},
+    },
+    network::{EdgeId, Network, NodeId},
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// Bidirectional search from `source` and `target` where `neighbours` picks the edges a settled
+/// node relaxes, which is how the hierarchical algorithms restrict their searches.
+///
+/// A search stops once its queue has no node closer than the best path found so far. Returns the
+/// cost in decimetres and the edges of the path, the edges are not unpacked.
+#[allow(clippy::result_unit_err)]
+pub fn bidirectional_path<N, F, I>(
+    network: &N,
+    neighbours: F,
+    source: NodeId,
+    target: NodeId,
+) -> Result<(u64, Vec<EdgeId>), ()>
+where
+    N: Network,
+    F: Fn(NodeId, &DijkstraDirection) -> I,
+    I: IntoIterator<Item = (NodeId, EdgeId)>,
+{
+    bidirectional_state_path(
+        network,
+        |_, _| (),
+        |node, _, direction| {
+            neighbours(node, direction)
+                .into_iter()
+                .map(|(neighbour, edge)| (neighbour, edge, ()))
+        },
+        source,
+        target,
+    )
+}
+
+/// `bidirectional_path` for searches whose labels also have a state, such as the level of a
+/// highway hierarchy search. A search starts with the state `start` gives and `neighbours` picks
+/// the edges a settled node relaxes from the state of its label, together with the state of the
+/// label each edge leads to. Of two labels with the same cost the one with the smallest state
+/// wins.
+#[allow(clippy::result_unit_err)]
+pub fn bidirectional_state_path<N, S, T, F, I>(
+    network: &N,
+    start: T,
+    neighbours: F,
+    source: NodeId,
+    target: NodeId,
+) -> Result<(u64, Vec<EdgeId>), ()>
+where
+    N: Network,
+    S: Copy + Ord,
+    T: Fn(NodeId, &DijkstraDirection) -> S,
+    F: Fn(NodeId, S, &DijkstraDirection) -> I,
+    I: IntoIterator<Item = (NodeId, EdgeId, S)>,
+{
+    let metric = Decimetres;
+    let forward_start = start(source, &DijkstraDirection::Forward);
+    let mut forward = LabelSearch::new(
+        network,
+        &metric,
+        &neighbours,
+        source,
+        forward_start,
+        DijkstraDirection::Forward,
+    );
+    let backward_start = start(target, &DijkstraDirection::Backward);
+    let mut backward = LabelSearch::new(
+        network,
+        &metric,
+        &neighbours,
+        target,
+        backward_start,
+        DijkstraDirection::Backward,
+    );
+
+    // Cost of the best path found so far and the node where both searches met.
+    let mut best: Option<(u64, NodeId)> = None;
+    meet(source, &forward, &backward, &mut best);
+
+    loop {
+        let limit = best.map_or(u64::MAX, |(cost, _)| cost);
+        let forward_cost = forward.peek_cost().filter(|cost| *cost < limit);
+        let backward_cost = backward.peek_cost().filter(|cost| *cost < limit);
+
+        let reached = match (forward_cost, backward_cost) {
+            (None, None) => break,
+            (Some(f), Some(b)) if b < f => backward.step(),
+            (Some(_), _) => forward.step(),
+            (None, Some(_)) => backward.step(),
+        };
+
+        for node in reached {
+            meet(node, &forward, &backward, &mut best);
+        }
+    }
+
+    join(&forward, &backward, best)
+}
+
+/// Bidirectional Dijkstra over all edges from `source` and `target`, minimising `metric`. The
+/// searches stop once the closest nodes in both queues together cost at least as much as the best
+/// path found so far, so that path is a shortest path. Fails when there is no path.
+#[allow(clippy::result_unit_err)]
+pub fn bidirectional_dijkstra<N: Network, M: Metric>(
+    network: &N,
+    metric: M,
+    source: NodeId,
+    target: NodeId,
+) -> Result<(M::Weight, Vec<EdgeId>), ()> {
+    let neighbours = |node, _, direction: &DijkstraDirection| {
+        direction
+            .neighbours(node, network)
+            .map(|(neighbour, edge)| (neighbour, edge, ()))
+    };
+    let mut forward = LabelSearch::new(
+        network,
+        &metric,
+        &neighbours,
+        source,
+        (),
+        DijkstraDirection::Forward,
+    );
+    let mut backward = LabelSearch::new(
+        network,
+        &metric,
+        &neighbours,
+        target,
+        (),
+        DijkstraDirection::Backward,
+    );
+
+    let mut best: Option<(M::Weight, NodeId)> = None;
+    meet(source, &forward, &backward, &mut best);
+
+    // Once either queue is empty, every node where the searches could meet has been labeled by
+    // both of them.
+    while let (Some(f), Some(b)) = (forward.peek_cost(), backward.peek_cost()) {
+        if best.is_some_and(|(cost, _)| f + b >= cost) {
+            break;
+        }
+
+        let reached = if f <= b {
+            forward.step()
+        } else {
+            backward.step()
+        };
+        for node in reached {
+            meet(node, &forward, &backward, &mut best);
+        }
+    }
+
+    join(&forward, &backward, best)
+}
+
+/// The path through the node where the searches met, when they did.
+fn join<N, M, S, F, I>(
+    forward: &LabelSearch<N, M, S, F>,
+    backward: &LabelSearch<N, M, S, F>,
+    best: Option<(M::Weight, NodeId)>,
+) -> Result<(M::Weight, Vec<EdgeId>), ()>
+where
+    N: Network,
+    M: Metric,
+    S: Copy + Ord,
+    F: Fn(NodeId, S, &DijkstraDirection) -> I,
+    I: IntoIterator<Item = (NodeId, EdgeId, S)>,
+{
+    let (cost, middle) = best.ok_or(())?;
+    let mut edges = forward.rebuild(middle);
+    edges.append(&mut backward.rebuild(middle));
+
+    Ok((cost, edges))
+}
+
+fn meet<N, M, S, F, I>(
+    node: NodeId,
+    forward: &LabelSearch<N, M, S, F>,
+    backward: &LabelSearch<N, M, S, F>,
+    best: &mut Option<(M::Weight, NodeId)>,
+) where
+    N: Network,
+    M: Metric,
+    S: Copy + Ord,
+    F: Fn(NodeId, S, &DijkstraDirection) -> I,
+    I: IntoIterator<Item = (NodeId, EdgeId, S)>,
+{
+    if let (Some(f), Some(b)) = (forward.cost(node), backward.cost(node)) {
+        if best.is_none_or(|(cost, _)| f + b < cost) {
+            *best = Some((f + b, node));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Label<W, S> {
+    cost: W,
+    state: S,
+    // Where it came from.
+    edge: Option<EdgeId>,
+    settled: bool,
+}
+
+struct LabelSearch<'a, N, M: Metric, S, F> {
+    network: &'a N,
+    metric: &'a M,
+    neighbours: &'a F,
+    direction: DijkstraDirection,
+    labels: HashMap<NodeId, Label<M::Weight, S>>,
+    heap: BinaryHeap<Reverse<(M::Weight, NodeId)>>,
+}
+
+impl<'a, N, M, S, F, I> LabelSearch<'a, N, M, S, F>
+where
+    N: Network,
+    M: Metric,
+    S: Copy + Ord,
+    F: Fn(NodeId, S, &DijkstraDirection) -> I,
+    I: IntoIterator<Item = (NodeId, EdgeId, S)>,
+{
+    fn new(
+        network: &'a N,
+        metric: &'a M,
+        neighbours: &'a F,
+        start: NodeId,
+        state: S,
+        direction: DijkstraDirection,
+    ) -> Self {
+        let mut labels = HashMap::new();
+        let mut heap = BinaryHeap::new();
+
+        let label = Label {
+            cost: M::Weight::ZERO,
+            state,
+            edge: None,
+            settled: false,
+        };
+        labels.insert(start, label);
+        heap.push(Reverse((M::Weight::ZERO, start)));
+
+        Self {
+            network,
+            metric,
+            neighbours,
+            direction,
+            labels,
+            heap,
+        }
+    }
+
+    fn cost(&self, node: NodeId) -> Option<M::Weight> {
+        self.labels.get(&node).map(|label| label.cost)
+    }
+
+    fn peek_cost(&mut self) -> Option<M::Weight> {
+        while let Some(Reverse((cost, node))) = self.heap.peek() {
+            let label = &self.labels[node];
+            if label.settled || label.cost != *cost {
+                self.heap.pop();
+            } else {
+                return Some(*cost);
+            }
+        }
+        None
+    }
+
+    /// Settles the next node, returns the nodes whose label improved.
+    fn step(&mut self) -> Vec<NodeId> {
+        let node = match self.peek_cost().and_then(|_| self.heap.pop()) {
+            Some(Reverse((_, node))) => node,
+            None => return Vec::new(),
+        };
+
+        let label = {
+            let label = self.labels.get_mut(&node).unwrap();
+            label.settled = true;
+            *label
+        };
+
+        let mut reached = Vec::new();
+
+        for (neighbour, edge, state) in (self.neighbours)(node, label.state, &self.direction) {
+            let new_label = Label {
+                cost: label.cost + self.metric.weight(self.network, edge),
+                state,
+                edge: Some(edge),
+                settled: false,
+            };
+
+            match self.labels.get_mut(&neighbour) {
+                Some(existing)
+                    if existing.settled
+                        || (existing.cost, existing.state) <= (new_label.cost, state) =>
+                {
+                    continue
+                }
+                Some(existing) => *existing = new_label,
+                None => {
+                    self.labels.insert(neighbour, new_label);
+                }
+            }
+
+            self.heap.push(Reverse((new_label.cost, neighbour)));
+            reached.push(neighbour);
+        }
+
+        reached
+    }
+
+    fn rebuild(&self, mut node: NodeId) -> Vec<EdgeId> {
+        let mut edges = Vec::new();
+        while let Some(edge) = self.labels[&node].edge {
+            edges.push(edge);
+            node = match self.direction {
+                DijkstraDirection::Forward => self.network.edge_source(edge),
+                DijkstraDirection::Backward => self.network.edge_target(edge),
+            };
+        }
+
+        if let DijkstraDirection::Forward = self.direction {
+            edges.reverse();
+        }
+
+        edges
+    }
+}
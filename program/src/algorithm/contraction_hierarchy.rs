@@ -0,0 +1,69 @@
+use super::{
+    bidirectional::bidirectional_path, pairwise_paths, EdgePath, ManyManyErrors,
+    ManyToManyAlgorithm, PathAlgorithm,
+};
+use crate::{
+    contraction::hierarchy::ContractionHierarchy,
+    network::{EdgeId, LiteNetwork, NodeId},
+};
+
+/// Bidirectional query on a contraction hierarchy.
+///
+/// Both searches only relax edges towards higher ranked nodes, so they meet in the highest ranked
+/// node of the shortest path. A search stops once its queue has no node closer than the best path
+/// found so far.
+pub struct ContractionHierarchyPathAlgorithm {
+    hierarchy: ContractionHierarchy,
+}
+
+impl ContractionHierarchyPathAlgorithm {
+    pub fn with_hierarchy(hierarchy: ContractionHierarchy) -> Self {
+        Self { hierarchy }
+    }
+
+    pub fn hierarchy(&self) -> &ContractionHierarchy {
+        &self.hierarchy
+    }
+}
+
+impl PathAlgorithm for ContractionHierarchyPathAlgorithm {
+    type Network = LiteNetwork;
+    type Output = u64;
+
+    fn path(&self, source: NodeId, target: NodeId) -> Result<(Self::Output, Vec<EdgeId>), ()> {
+        let (cost, edges) = bidirectional_path(
+            self.hierarchy.network(),
+            |node, direction| self.hierarchy.upward_neighbours(node, direction),
+            source,
+            target,
+        )?;
+
+        let path = self.hierarchy.unpack_path(&EdgePath {
+            source,
+            target,
+            edges,
+        });
+
+        Ok((cost, path.edges))
+    }
+}
+
+impl ManyToManyAlgorithm for ContractionHierarchyPathAlgorithm {
+    type Network = LiteNetwork;
+
+    /// Panics when a shortcut is too long to store, `with_hierarchy` takes a hierarchy that is
+    /// built without panicking.
+    fn new(network: Self::Network) -> Self {
+        Self {
+            hierarchy: ContractionHierarchy::build(network).expect("shortcut is too long"),
+        }
+    }
+
+    fn network(&self) -> &Self::Network {
+        self.hierarchy.network()
+    }
+
+    fn path(&self, nodes: &[NodeId]) -> Result<Vec<EdgePath>, ManyManyErrors> {
+        pairwise_paths(self, nodes)
+    }
+}
@@ -7,12 +7,53 @@ use rayon::prelude::*;
 
 use crate::network::{EdgeId, LiteNetwork, Network, NodeId};
 
-use super::{EdgePath, ManyManyErrors, ManyToManyAlgorithm};
+use super::{
+    weight::{Decimetres, Metric, Weight},
+    EdgePath, ManyManyErrors, ManyToManyAlgorithm, PathAlgorithm,
+};
 
 pub struct DijkstraPathAlgorithm {
     network: LiteNetwork,
 }
 
+impl PathAlgorithm for DijkstraPathAlgorithm {
+    type Network = LiteNetwork;
+    type Output = u64;
+
+    fn path(&self, source: NodeId, target: NodeId) -> Result<(Self::Output, Vec<EdgeId>), ()> {
+        self.path_with(Decimetres, source, target)
+    }
+}
+
+impl DijkstraPathAlgorithm {
+    /// The path that is shortest by `metric`.
+    #[allow(clippy::result_unit_err)]
+    pub fn path_with<M: Metric>(
+        &self,
+        metric: M,
+        source: NodeId,
+        target: NodeId,
+    ) -> Result<(M::Weight, Vec<EdgeId>), ()> {
+        dijkstra_path(&self.network, metric, source, target)
+    }
+}
+
+/// Dijkstra from `source` until `target` is reached, minimising `metric`. Fails when there is no
+/// path.
+#[allow(clippy::result_unit_err)]
+pub fn dijkstra_path<N: Network, M: Metric>(
+    network: &N,
+    metric: M,
+    source: NodeId,
+    target: NodeId,
+) -> Result<(M::Weight, Vec<EdgeId>), ()> {
+    let mut search =
+        DijkstraIterator::with_metric(network, metric, source, DijkstraDirection::Forward);
+    let (cost, _) = search.find(|(_, node)| *node == target).ok_or(())?;
+
+    Ok((cost, search.rebuild(target)))
+}
+
 impl ManyToManyAlgorithm for DijkstraPathAlgorithm {
     type Network = LiteNetwork;
 
@@ -57,8 +98,6 @@ impl ManyToManyAlgorithm for DijkstraPathAlgorithm {
                     }
                 }
 
-                println!("{:?}", node);
-
                 let mut found_paths = Vec::new();
 
                 for j in 0..nodes.len() {
@@ -87,28 +126,42 @@ impl ManyToManyAlgorithm for DijkstraPathAlgorithm {
     }
 }
 
-pub struct DijkstraIterator<'a, T: Network> {
-    visited: HashMap<NodeId, (usize, Option<EdgeId>)>, // The visited node id -> (the current cost, where it came from)
-    heap: BinaryHeap<Reverse<DijkstraIteratorEntry>>,
+/// Dijkstra's search from one node, yielding every reachable node with its cost in the order they
+/// are settled. Costs are whole decimetres unless the search is made `with_metric`.
+pub struct DijkstraIterator<'a, T: Network, M: Metric = Decimetres> {
+    visited: HashMap<NodeId, (M::Weight, Option<EdgeId>)>, // The visited node id -> (the current cost, where it came from)
+    heap: BinaryHeap<Reverse<DijkstraIteratorEntry<M::Weight>>>,
     direction: DijkstraDirection,
     network: &'a T,
+    metric: M,
 }
 
 impl<'a, T: Network> DijkstraIterator<'a, T> {
     pub fn new(network: &'a T, start: NodeId, direction: DijkstraDirection) -> Self {
+        Self::with_metric(network, Decimetres, start, direction)
+    }
+}
+
+impl<'a, T: Network, M: Metric> DijkstraIterator<'a, T, M> {
+    pub fn with_metric(
+        network: &'a T,
+        metric: M,
+        start: NodeId,
+        direction: DijkstraDirection,
+    ) -> Self {
         let mut initial_heap = BinaryHeap::new();
 
         initial_heap.push(Reverse(DijkstraIteratorEntry {
             node: start,
-            cost: 0,
+            cost: M::Weight::ZERO,
             edge: None,
         }));
-        // initial_map.insert(start, (0f32, None));
         DijkstraIterator {
             visited: HashMap::new(),
             heap: initial_heap,
             direction,
             network,
+            metric,
         }
     }
 
@@ -126,20 +179,20 @@ impl<'a, T: Network> DijkstraIterator<'a, T> {
             edges.reverse();
         }
 
-        return edges;
+        edges
     }
 
-    pub fn peek_cost(&self) -> Option<usize> {
+    pub fn peek_cost(&self) -> Option<M::Weight> {
         self.heap.peek().map(|x| x.0.cost)
     }
 
-    pub fn visited(&self) -> &HashMap<NodeId, (usize, Option<EdgeId>)> {
+    pub fn visited(&self) -> &HashMap<NodeId, (M::Weight, Option<EdgeId>)> {
         &self.visited
     }
 }
 
-impl<'a, T: Network> Iterator for DijkstraIterator<'a, T> {
-    type Item = (usize, NodeId);
+impl<'a, T: Network, M: Metric> Iterator for DijkstraIterator<'a, T, M> {
+    type Item = (M::Weight, NodeId);
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut entry = self.heap.pop()?.0;
@@ -154,20 +207,22 @@ impl<'a, T: Network> Iterator for DijkstraIterator<'a, T> {
         self.visited.insert(entry.node, (entry.cost, entry.edge));
 
         for (neighbour, edge) in self.direction.neighbours(entry.node, self.network) {
-            let cost = entry.cost + self.network.edge_distance(edge) as usize;
+            let cost = entry.cost + self.metric.weight(self.network, edge);
             debug_assert!(cost >= entry.cost);
 
             self.heap.push(Reverse(DijkstraIteratorEntry {
                 node: neighbour,
-                cost: cost,
+                cost,
                 edge: Some(edge),
             }));
         }
 
         debug_assert!(
-            self.heap.peek().unwrap().0.cost >= entry.cost,
-            "{} > {}",
-            self.heap.peek().unwrap().0.cost,
+            self.heap
+                .peek()
+                .is_none_or(|next| next.0.cost >= entry.cost),
+            "{:?} > {:?}",
+            self.heap.peek(),
             entry.cost
         );
         Some((entry.cost, entry.node))
@@ -175,30 +230,27 @@ impl<'a, T: Network> Iterator for DijkstraIterator<'a, T> {
 }
 
 #[derive(Debug, PartialEq)]
-struct DijkstraIteratorEntry {
+struct DijkstraIteratorEntry<W> {
     node: NodeId,
-    cost: usize,
+    cost: W,
     edge: Option<EdgeId>,
 }
 
-impl PartialOrd for DijkstraIteratorEntry {
+impl<W: Weight> PartialOrd for DijkstraIteratorEntry<W> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.cost
-            .partial_cmp(&other.cost)
-            .map(|x| x.then(self.node.0.cmp(&other.node.0)))
+        Some(self.cmp(other))
     }
 }
 
-impl Ord for DijkstraIteratorEntry {
+impl<W: Weight> Ord for DijkstraIteratorEntry<W> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.cost
-            .partial_cmp(&other.cost)
-            .unwrap()
+            .cmp(&other.cost)
             .then(self.node.0.cmp(&other.node.0))
     }
 }
 
-impl Eq for DijkstraIteratorEntry {}
+impl<W: Weight> Eq for DijkstraIteratorEntry<W> {}
 
 #[derive(Debug)]
 pub enum DijkstraDirection {
@@ -207,18 +259,24 @@ pub enum DijkstraDirection {
 }
 
 impl DijkstraDirection {
-    pub fn neighbours<T: Network>(&self, node: NodeId, network: &T) -> Vec<(NodeId, EdgeId)> {
-        match self {
-            DijkstraDirection::Forward => network
-                .outgoing_edges(node)
-                .iter()
-                .map(|edge| (network.edge_target(*edge), *edge))
-                .collect::<Vec<_>>(),
-            DijkstraDirection::Backward => network
-                .incoming_edges(node)
-                .iter()
-                .map(|edge| (network.edge_source(*edge), *edge))
-                .collect::<Vec<_>>(),
-        }
+    /// The edges that leave (forward) or enter (backward) `node`, together with the node on the
+    /// other end.
+    pub fn neighbours<'a, T: Network>(
+        &self,
+        node: NodeId,
+        network: &'a T,
+    ) -> impl Iterator<Item = (NodeId, EdgeId)> + 'a {
+        let (edges, forward) = match self {
+            DijkstraDirection::Forward => (network.outgoing_edges(node), true),
+            DijkstraDirection::Backward => (network.incoming_edges(node), false),
+        };
+
+        edges.iter().map(move |edge| {
+            if forward {
+                (network.edge_target(*edge), *edge)
+            } else {
+                (network.edge_source(*edge), *edge)
+            }
+        })
     }
 }
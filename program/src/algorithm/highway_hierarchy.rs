@@ -0,0 +1,128 @@
+use super::{
+    bidirectional::bidirectional_state_path,
+    pairwise_paths,
+    weight::{Decimetres, Metric},
+    EdgePath, ManyManyErrors, ManyToManyAlgorithm, PathAlgorithm,
+};
+use crate::{
+    algorithm::dijkstra::DijkstraDirection,
+    highway::{HighwayHierarchy, HighwayParameters, INFINITE_RADIUS},
+    network::{EdgeId, LiteNetwork, NodeId},
+};
+use std::cmp::Reverse;
+
+/// Bidirectional query on a highway hierarchy.
+///
+/// Both searches start in level 0. Once a search leaves the neighbourhood of the node where it
+/// entered the current level it moves up a level, and edges below that level are skipped.
+pub struct HighwayHierarchyPathAlgorithm {
+    network: LiteNetwork,
+    hierarchy: HighwayHierarchy,
+}
+
+impl HighwayHierarchyPathAlgorithm {
+    pub fn with_hierarchy(network: LiteNetwork, hierarchy: HighwayHierarchy) -> Self {
+        Self { network, hierarchy }
+    }
+
+    pub fn hierarchy(&self) -> &HighwayHierarchy {
+        &self.hierarchy
+    }
+}
+
+impl PathAlgorithm for HighwayHierarchyPathAlgorithm {
+    type Network = LiteNetwork;
+    type Output = u64;
+
+    fn path(&self, source: NodeId, target: NodeId) -> Result<(Self::Output, Vec<EdgeId>), ()> {
+        bidirectional_state_path(
+            &self.network,
+            |node, direction| LevelState {
+                level: 0,
+                gap: Reverse(radius(direction)(&self.hierarchy, 0, node)),
+            },
+            |node, state, direction| self.level_neighbours(node, state, direction),
+            source,
+            target,
+        )
+    }
+}
+
+impl ManyToManyAlgorithm for HighwayHierarchyPathAlgorithm {
+    type Network = LiteNetwork;
+
+    fn new(network: Self::Network) -> Self {
+        let hierarchy = HighwayHierarchy::build(&network, HighwayParameters::default());
+        Self { network, hierarchy }
+    }
+
+    fn network(&self) -> &Self::Network {
+        &self.network
+    }
+
+    fn path(&self, nodes: &[NodeId]) -> Result<Vec<EdgePath>, ManyManyErrors> {
+        pairwise_paths(self, nodes)
+    }
+}
+
+/// The search state of a label: the level of the search and the distance left before it leaves
+/// the neighbourhood of its entrance node. With equal costs the least restrictive state wins, it
+/// orders first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct LevelState {
+    level: u8,
+    gap: Reverse<u64>,
+}
+
+/// The neighbourhood radius of a node on a level, for a search in `direction`.
+fn radius(direction: &DijkstraDirection) -> fn(&HighwayHierarchy, u8, NodeId) -> u64 {
+    match direction {
+        DijkstraDirection::Forward => HighwayHierarchy::forward_radius,
+        DijkstraDirection::Backward => HighwayHierarchy::backward_radius,
+    }
+}
+
+impl HighwayHierarchyPathAlgorithm {
+    /// The edges a search relaxes from `node` with `state`. An edge longer than the gap moves the
+    /// search up a level, and edges below the level of the search are skipped.
+    fn level_neighbours<'a>(
+        &'a self,
+        node: NodeId,
+        state: LevelState,
+        direction: &DijkstraDirection,
+    ) -> impl Iterator<Item = (NodeId, EdgeId, LevelState)> + 'a {
+        let radius = radius(direction);
+        direction
+            .neighbours(node, &self.network)
+            .filter_map(move |(neighbour, edge)| {
+                let distance = Decimetres.weight(&self.network, edge);
+
+                let LevelState {
+                    mut level,
+                    gap: Reverse(mut gap),
+                } = state;
+                while distance > gap {
+                    level += 1;
+                    gap = radius(&self.hierarchy, level, node);
+                }
+
+                if self.hierarchy.edge_level(edge) < level {
+                    return None;
+                }
+
+                let gap = if gap == INFINITE_RADIUS {
+                    INFINITE_RADIUS
+                } else {
+                    gap - distance
+                };
+                Some((
+                    neighbour,
+                    edge,
+                    LevelState {
+                        level,
+                        gap: Reverse(gap),
+                    },
+                ))
+            })
+    }
+}
@@ -0,0 +1,106 @@
+use super::{
+    bidirectional::bidirectional_path, pairwise_paths, EdgePath, ManyManyErrors,
+    ManyToManyAlgorithm, PathAlgorithm,
+};
+use crate::{
+    highway::{HighwayHierarchy, HighwayParameters},
+    network::{builder::DistanceError, EdgeId, LiteNetwork, Network, NodeId},
+    node_routing::MultiLevelOverlay,
+};
+use std::cmp::Reverse;
+
+/// Bidirectional query on the overlay graphs of highway node routing.
+///
+/// A search only relaxes the edges of the highest overlay graph the settled node belongs to, so
+/// both searches climb the levels as soon as they reach highway nodes. A search stops once its
+/// queue has no node closer than the best path found so far.
+pub struct HighwayNodeRoutingPathAlgorithm {
+    overlay: MultiLevelOverlay,
+}
+
+impl HighwayNodeRoutingPathAlgorithm {
+    pub fn with_overlay(overlay: MultiLevelOverlay) -> Self {
+        Self { overlay }
+    }
+
+    pub fn overlay(&self) -> &MultiLevelOverlay {
+        &self.overlay
+    }
+
+    /// Changes the distance of original edges, see `MultiLevelOverlay::update_edge_distances`.
+    pub fn update_edge_distances(
+        &mut self,
+        changes: &[(EdgeId, f32)],
+    ) -> Result<(), DistanceError> {
+        self.overlay.update_edge_distances(changes)
+    }
+
+    /// Ranks the nodes by the highest level they reach in a highway hierarchy, every level of
+    /// the hierarchy becomes a level of highway nodes.
+    pub fn hierarchy_ranking(
+        network: &LiteNetwork,
+        hierarchy: &HighwayHierarchy,
+    ) -> (Vec<NodeId>, Vec<usize>) {
+        let node_levels = hierarchy.node_levels(network);
+
+        let mut ranking = (0..network.nodes_len())
+            .map(NodeId::new)
+            .collect::<Vec<_>>();
+        ranking.sort_by_key(|node| Reverse(node_levels[node.index()]));
+
+        let level_sizes = (1..=hierarchy.top_level())
+            .map(|level| node_levels.iter().filter(|x| **x >= level).count())
+            .collect();
+
+        (ranking, level_sizes)
+    }
+}
+
+impl PathAlgorithm for HighwayNodeRoutingPathAlgorithm {
+    type Network = LiteNetwork;
+    type Output = u64;
+
+    fn path(&self, source: NodeId, target: NodeId) -> Result<(Self::Output, Vec<EdgeId>), ()> {
+        let (cost, edges) = bidirectional_path(
+            self.overlay.network(),
+            |node, direction| {
+                self.overlay
+                    .level_neighbours(node, self.overlay.node_level(node), direction)
+            },
+            source,
+            target,
+        )?;
+
+        let path = self.overlay.unpack_path(&EdgePath {
+            source,
+            target,
+            edges,
+        });
+
+        Ok((cost, path.edges))
+    }
+}
+
+impl ManyToManyAlgorithm for HighwayNodeRoutingPathAlgorithm {
+    type Network = LiteNetwork;
+
+    /// Panics when an overlay edge is too long to store, `with_overlay` takes an overlay that is
+    /// built without panicking.
+    fn new(network: Self::Network) -> Self {
+        let hierarchy = HighwayHierarchy::build(&network, HighwayParameters::default());
+        let (ranking, level_sizes) = Self::hierarchy_ranking(&network, &hierarchy);
+
+        Self {
+            overlay: MultiLevelOverlay::build(network, &ranking, &level_sizes)
+                .expect("overlay edge is too long"),
+        }
+    }
+
+    fn network(&self) -> &Self::Network {
+        self.overlay.network()
+    }
+
+    fn path(&self, nodes: &[NodeId]) -> Result<Vec<EdgePath>, ManyManyErrors> {
+        pairwise_paths(self, nodes)
+    }
+}
@@ -1,8 +1,19 @@
 use crate::network::{EdgeId, Network, NodeId};
+use rayon::prelude::*;
 
-pub mod dijkstra_bi_dir;
+pub mod bidirectional;
+pub mod contraction_hierarchy;
 pub mod dijkstra;
+pub mod dijkstra_bi_dir;
+pub mod highway_hierarchy;
+pub mod highway_node_routing;
+pub mod region;
 pub mod simple_a_star;
+pub mod verify;
+pub mod weight;
+
+#[cfg(test)]
+mod tests;
 
 pub fn many_to_many_paths<S: Network, T: ManyToManyAlgorithm<Network = S>>(
     nodes: &[NodeId],
@@ -12,10 +23,46 @@ pub fn many_to_many_paths<S: Network, T: ManyToManyAlgorithm<Network = S>>(
     algorithm.path(nodes)
 }
 
+/// The paths between all pairs of `nodes`, found by a separate query of `algorithm` for every
+/// pair. The pairs are searched in parallel.
+pub fn pairwise_paths<A: PathAlgorithm + Sync>(
+    algorithm: &A,
+    nodes: &[NodeId],
+) -> Result<Vec<EdgePath>, ManyManyErrors> {
+    if nodes.is_empty() {
+        return Err(ManyManyErrors::EmptyNodeList);
+    }
+
+    let found_paths = nodes
+        .par_iter()
+        .flat_map_iter(|source| {
+            nodes
+                .iter()
+                .filter(move |target| *target != source)
+                .filter_map(move |target| {
+                    let (_, edges) = algorithm.path(*source, *target).ok()?;
+                    Some(EdgePath {
+                        source: *source,
+                        target: *target,
+                        edges,
+                    })
+                })
+        })
+        .collect::<Vec<_>>();
+
+    // All pairs should be found, excluding path to own.
+    if found_paths.len() == nodes.len() * (nodes.len() - 1) {
+        Ok(found_paths)
+    } else {
+        Err(ManyManyErrors::NotAllPairsFound(found_paths))
+    }
+}
+
 pub trait PathAlgorithm {
     type Network: Network;
     type Output;
 
+    #[allow(clippy::result_unit_err)]
     fn path(&self, source: NodeId, target: NodeId) -> Result<(Self::Output, Vec<EdgeId>), ()>;
 }
 
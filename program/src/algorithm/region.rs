@@ -0,0 +1,39 @@
+use super::{bidirectional::bidirectional_path, PathAlgorithm};
+use crate::{
+    network::{EdgeId, NodeId},
+    region::RegionNetwork,
+};
+
+/// Highway node routing query on a network that is loaded region by region. The path consists of
+/// edges of the region network, `RegionNetwork::original_edges` unpacks them.
+///
+/// A query also fails when a cell could not be read, `RegionNetwork::check_loaded_cells` says why.
+pub struct RegionPathAlgorithm {
+    network: RegionNetwork,
+}
+
+impl RegionPathAlgorithm {
+    pub fn with_network(network: RegionNetwork) -> Self {
+        Self { network }
+    }
+
+    pub fn network(&self) -> &RegionNetwork {
+        &self.network
+    }
+}
+
+impl PathAlgorithm for RegionPathAlgorithm {
+    type Network = RegionNetwork;
+    type Output = u64;
+
+    fn path(&self, source: NodeId, target: NodeId) -> Result<(Self::Output, Vec<EdgeId>), ()> {
+        let path = bidirectional_path(
+            &self.network,
+            |node, direction| direction.neighbours(node, &self.network),
+            source,
+            target,
+        );
+        self.network.check_loaded_cells().map_err(|_| ())?;
+        path
+    }
+}
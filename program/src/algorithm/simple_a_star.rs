@@ -1,27 +1,47 @@
-use crate::network::{EdgeId, Network, NodeCoord, NodeId};
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::network::{EdgeId, Network, NodeId};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
 
-use super::PathAlgorithm;
+use super::{
+    weight::{Decimetres, Metric, Weight},
+    PathAlgorithm,
+};
 
 pub struct SimpleAStar<A>
 where
     A: Network,
 {
     network: A,
+    distance: StraightLineBound<Decimetres>,
 }
 
-#[allow(dead_code)]
 impl<A> SimpleAStar<A>
 where
     A: Network,
 {
     pub fn new(network: A) -> Self {
-        SimpleAStar { network }
+        let distance = StraightLineBound::new(&network, Decimetres);
+        SimpleAStar { network, distance }
     }
 
     pub fn network(&self) -> &A {
         &self.network
     }
+
+    /// The path that is shortest by `metric`. The bound of `metric` is built from every edge on
+    /// each call, keep a `StraightLineBound` for `a_star_path` to search more than once.
+    #[allow(clippy::result_unit_err)]
+    pub fn path_with<M: Metric>(
+        &self,
+        metric: M,
+        source: NodeId,
+        target: NodeId,
+    ) -> Result<(M::Weight, Vec<EdgeId>), ()> {
+        let bound = StraightLineBound::new(&self.network, metric);
+        a_star_path(&self.network, &bound, source, target)
+    }
 }
 
 impl<A> PathAlgorithm for SimpleAStar<A>
@@ -29,127 +49,111 @@ where
     A: Network,
 {
     type Network = A;
-    type Output = ();
-
-    fn path(
-        &self,
-        source: crate::network::NodeId,
-        target: crate::network::NodeId,
-    ) -> Result<(Self::Output, Vec<crate::network::EdgeId>), ()> {
-        {
-            let start_node = self.network.junction_id(source);
-            let end_node = self.network.junction_id(target);
-            let distance = self
-                .network
-                .node_location(source)
-                .distance(&self.network.node_location(target));
-
-            println!(
-                "Finding path from: {}({}) towards {}({}), around: {} meters",
-                start_node, source.0, end_node, target.0, distance
-            );
-        }
-
-        let source_coord = self.network.node_location(source);
-        let target_coord = self.network.node_location(target);
-
-        let mut from: HashMap<NodeId, (f32, Option<EdgeId>)> = HashMap::new();
-        from.insert(source, (0.0, None));
+    type Output = u64;
 
-        let mut heap = BinaryHeap::new();
-
-        heap.push(HeapEntry {
-            cost: heuristic(0f32, source_coord, target_coord),
-            distance: 0f32,
-            node: source,
-        });
-
-        let mut visited = HashSet::new();
-
-        while let Some(entry) = heap.pop() {
-            if entry.node == target {
-                let mut m = target;
-                let mut edges = Vec::new();
-                while let (_, Some(prev)) = from[&m] {
-                    edges.push(prev);
-                    let s = self.network.edge_source(prev);
-                    m = s;
-                }
-                edges.reverse();
-                return Ok(((), edges));
-            }
+    fn path(&self, source: NodeId, target: NodeId) -> Result<(Self::Output, Vec<EdgeId>), ()> {
+        a_star_path(&self.network, &self.distance, source, target)
+    }
+}
 
-            if !visited.insert(entry.node) {
-                continue;
-            }
+/// A lower bound of the cost by a metric between two nodes of a network, from the straight line
+/// between them. Weights are rounded to whole units, so an edge can cost a little less than its
+/// straight line and a path of many short edges a lot less. The straight line is scaled down by
+/// the least share of its own straight line that any edge costs.
+#[derive(Debug, Clone, Copy)]
+pub struct StraightLineBound<M> {
+    metric: M,
+    factor: f64,
+}
 
-            // println!("--");
-            // println!("node: {:?}", entry);
-
-            let children = self.network.outgoing_edges(entry.node);
-            let distances = children
-                .iter()
-                .map(|x| self.network.edge_distance(*x))
-                .collect::<Vec<_>>();
-            let neighbours = children
-                .iter()
-                .map(|x| self.network.edge_target(*x))
-                .collect::<Vec<_>>();
-            let locations = neighbours
-                .iter()
-                .map(|x| self.network.node_location(*x))
-                .collect::<Vec<_>>();
-
-            for (child, edge) in neighbours
-                .into_iter()
-                .zip(locations.into_iter())
-                .zip(distances.into_iter())
-                .zip(children.iter())
-                .map(|(((node, coords), distance), edge)| {
-                    (
-                        HeapEntry {
-                            node,
-                            distance: entry.distance + distance,
-                            cost: heuristic(entry.distance + distance, coords, target_coord),
-                        },
-                        *edge,
-                    )
-                })
-            {
-                // println!("child: {:?}", child);
-                let ndist = entry.distance + self.network.edge_distance(edge);
-                if let Some((d, x)) = from.get_mut(&child.node) {
-                    if ndist < *d {
-                        *d = ndist;
-                        *x = Some(edge);
-                    }
+impl<M: Metric> StraightLineBound<M> {
+    pub fn new<N: Network>(network: &N, metric: M) -> Self {
+        let factor = (0..network.edge_len())
+            .map(EdgeId::new)
+            .fold(1.0_f64, |factor, edge| {
+                let source = network.node_location(network.edge_source(edge));
+                let target = network.node_location(network.edge_target(edge));
+                let straight = metric.straight_line(f64::from(source.distance(&target)));
+                if straight > 0.0 {
+                    factor.min(metric.weight(network, edge).to_f64() / straight)
                 } else {
-                    from.insert(child.node, (ndist, Some(edge)));
+                    factor
                 }
-                heap.push(child);
-            }
+            });
+        StraightLineBound {
+            metric,
+            // Distances between f32 locations are not exact, so a path can be a little shorter
+            // than the straight line computed between its ends.
+            factor: factor * (1.0 - STRAIGHT_LINE_ERROR),
         }
+    }
 
-        Err(())
+    pub fn metric(&self) -> &M {
+        &self.metric
     }
-}
 
-fn heuristic(cost: f32, source: NodeCoord, target: NodeCoord) -> f32 {
-    // cost + source.distance(&target)
-    -(cost + source.distance(&target))
+    /// The least any path costs between two points `metres` apart.
+    pub fn estimate(&self, metres: f32) -> M::Weight {
+        let straight = self.metric.straight_line(f64::from(metres));
+        M::Weight::floor((self.factor * straight).max(0.0))
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
-struct HeapEntry {
-    cost: f32,
-    distance: f32,
-    node: NodeId,
-}
+/// Relative error allowed in the straight line distances.
+const STRAIGHT_LINE_ERROR: f64 = 1e-6;
+
+/// A* from `source` to `target`, minimising the metric of `bound`. Nodes are searched in the order
+/// of their cost plus the bound of the rest of the way to `target`. The bound never overestimates
+/// but does not have to grow by at most an edge weight per edge, so a node is searched again when
+/// a cheaper path to it is found later. Fails when there is no path.
+#[allow(clippy::result_unit_err)]
+pub fn a_star_path<N: Network, M: Metric>(
+    network: &N,
+    bound: &StraightLineBound<M>,
+    source: NodeId,
+    target: NodeId,
+) -> Result<(M::Weight, Vec<EdgeId>), ()> {
+    let metric = bound.metric();
+    let target_location = network.node_location(target);
+    let estimate = |cost: M::Weight, node: NodeId| {
+        cost + bound.estimate(network.node_location(node).distance(&target_location))
+    };
+
+    // The node -> (the lowest cost found, where it came from)
+    let mut from: HashMap<NodeId, (M::Weight, Option<EdgeId>)> = HashMap::new();
+    from.insert(source, (M::Weight::ZERO, None));
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((estimate(M::Weight::ZERO, source), source)));
+
+    while let Some(Reverse((key, node))) = heap.pop() {
+        let cost = from[&node].0;
+        // A cheaper path to the node was found after this entry.
+        if key != estimate(cost, node) {
+            continue;
+        }
 
-impl Ord for HeapEntry {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.cost.partial_cmp(&other.cost).unwrap()
+        if node == target {
+            let mut edges = Vec::new();
+            let mut node = target;
+            while let (_, Some(edge)) = from[&node] {
+                edges.push(edge);
+                node = network.edge_source(edge);
+            }
+            edges.reverse();
+            return Ok((cost, edges));
+        }
+
+        for edge in network.outgoing_edges(node) {
+            let neighbour = network.edge_target(*edge);
+            let neighbour_cost = cost + metric.weight(network, *edge);
+            match from.get(&neighbour) {
+                Some((known, _)) if *known <= neighbour_cost => continue,
+                _ => {}
+            }
+            from.insert(neighbour, (neighbour_cost, Some(*edge)));
+            heap.push(Reverse((estimate(neighbour_cost, neighbour), neighbour)));
+        }
     }
-}
 
-impl Eq for HeapEntry {}
+    Err(())
+}
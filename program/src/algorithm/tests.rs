@@ -0,0 +1,732 @@
+// Point to point algorithms on generated networks, compared with shortest distances that are
+// known or computed independently. Costs are whole decimetres, so they compare exactly. The
+// algorithms with preprocessing are compared with Dijkstra.
+use super::{
+    contraction_hierarchy::ContractionHierarchyPathAlgorithm,
+    dijkstra::{DijkstraDirection, DijkstraIterator, DijkstraPathAlgorithm},
+    dijkstra_bi_dir::BiDirDijkstraPathAlgorithm,
+    highway_hierarchy::HighwayHierarchyPathAlgorithm,
+    highway_node_routing::HighwayNodeRoutingPathAlgorithm,
+    region::RegionPathAlgorithm,
+    simple_a_star::SimpleAStar,
+    verify::{self, Mismatch, PathFn, Problem},
+    weight::{self, Decimetres, Metres, TravelTime},
+    EdgePath, ManyManyErrors, ManyToManyAlgorithm, PathAlgorithm,
+};
+use crate::{
+    contraction::hierarchy::ContractionHierarchy,
+    f32_wrapper::F32Wrapper,
+    highway::{HighwayHierarchy, HighwayParameters},
+    network::{
+        builder::NetworkBuilder, generate, EdgeId, LiteNetwork, LiteNetworkBuilder, Network,
+        NodeCoord, NodeId,
+    },
+    node_routing::MultiLevelOverlay,
+    region::{self, RegionNetwork, RegionParameters},
+};
+use rand::{prelude::StdRng, Rng, SeedableRng};
+use std::{collections::HashMap, fs, path::Path};
+
+/// Runs every algorithm from `source` to `target` and checks the paths cost `expected`
+/// decimetres, or that none is found when it is `None`.
+fn check_paths(network: &LiteNetwork, source: NodeId, target: NodeId, expected: Option<u64>) {
+    let dijkstra = DijkstraPathAlgorithm::new(network.clone());
+    let bidirectional = BiDirDijkstraPathAlgorithm::new(network.clone());
+    let a_star = SimpleAStar::new(network.clone());
+
+    let found = [
+        ("dijkstra", PathAlgorithm::path(&dijkstra, source, target)),
+        (
+            "bidirectional",
+            PathAlgorithm::path(&bidirectional, source, target),
+        ),
+        ("a-star", a_star.path(source, target)),
+    ];
+
+    for (name, path) in found {
+        let context = format!("{} from {:?} to {:?}", name, source, target);
+        let expected = match expected {
+            Some(expected) => expected,
+            None => {
+                assert!(path.is_err(), "{} found a path", context);
+                continue;
+            }
+        };
+
+        let (cost, edges) = path.unwrap_or_else(|_| panic!("{} found no path", context));
+        assert_eq!(cost, expected, "{}", context);
+        assert_eq!(
+            path_length(network, source, &edges),
+            Some(expected),
+            "{}",
+            context
+        );
+        let end = edges
+            .last()
+            .map_or(source, |edge| network.edge_target(*edge));
+        assert_eq!(end, target, "{}", context);
+    }
+}
+
+/// Length of a path of edges that starts at `source`, `None` when the edges are not connected.
+fn path_length(network: &LiteNetwork, source: NodeId, edges: &[EdgeId]) -> Option<u64> {
+    let mut node = source;
+    for edge in edges {
+        if network.edge_source(*edge) != node {
+            return None;
+        }
+        node = network.edge_target(*edge);
+    }
+    Some(weight::path_weight(network, Decimetres, edges))
+}
+
+/// Shortest distances between all nodes by Floyd-Warshall, independent of the searches.
+fn all_distances(network: &LiteNetwork) -> Vec<Vec<Option<u64>>> {
+    let n = network.nodes_len();
+    let mut distances = vec![vec![None; n]; n];
+    for (node, row) in distances.iter_mut().enumerate() {
+        row[node] = Some(0);
+    }
+    for edge in (0..network.edge_len()).map(EdgeId::new) {
+        let (source, target) = (network.edge_source(edge), network.edge_target(edge));
+        let distance = u64::from(network.edge_decimetres(edge));
+        let known = &mut distances[source.index()][target.index()];
+        *known = Some(known.map_or(distance, |known| known.min(distance)));
+    }
+
+    for via in 0..n {
+        for source in 0..n {
+            for target in 0..n {
+                if let (Some(a), Some(b)) = (distances[source][via], distances[via][target]) {
+                    let known = &mut distances[source][target];
+                    *known = Some(known.map_or(a + b, |known| known.min(a + b)));
+                }
+            }
+        }
+    }
+    distances
+}
+
+#[test]
+fn grid_paths_follow_the_manhattan_distance() {
+    let (width, height) = (6, 5);
+    let network = generate::grid(LiteNetworkBuilder::new(), width, height, 100.0);
+    assert_eq!(network.nodes_len(), width * height);
+    assert_eq!(
+        network.edge_len(),
+        2 * ((width - 1) * height + width * (height - 1))
+    );
+
+    for source in 0..width * height {
+        for target in 0..width * height {
+            let (sx, sy) = (source % width, source / width);
+            let (tx, ty) = (target % width, target / width);
+            let manhattan = (sx as isize - tx as isize).abs() + (sy as isize - ty as isize).abs();
+            check_paths(
+                &network,
+                NodeId::new(source),
+                NodeId::new(target),
+                Some(manhattan as u64 * 1000),
+            );
+        }
+    }
+}
+
+#[test]
+fn random_geometric_paths_are_shortest() {
+    // Also short roads of a few metres, where the rounding of every road adds up along a path.
+    for (side, radius) in [(1000.0, 250.0), (20.0, 5.0)] {
+        for seed in 0..3 {
+            let network =
+                generate::random_geometric(LiteNetworkBuilder::new(), 60, side, radius, seed);
+            let distances = all_distances(&network);
+
+            for source in (0..network.nodes_len()).map(NodeId::new) {
+                for target in (0..network.nodes_len()).step_by(7).map(NodeId::new) {
+                    let expected = distances[source.index()][target.index()];
+                    check_paths(&network, source, target, expected);
+                }
+            }
+        }
+    }
+}
+
+/// The direct road from 0 to 3 is longer than going around through 1 and 2, node 4 can only be
+/// left towards 0 and node 5 is not connected at all. Edges are numbered in the order they are
+/// listed.
+fn detour_network() -> LiteNetwork {
+    let locations = [
+        (0.0, 0.0),
+        (100.0, 0.0),
+        (200.0, 0.0),
+        (300.0, 0.0),
+        (0.0, 100.0),
+        (500.0, 500.0),
+    ];
+    let edges = [
+        (0, 1, 100.0),
+        (1, 2, 100.0),
+        (2, 3, 100.0),
+        (0, 3, 350.0),
+        (3, 0, 300.0),
+        (4, 0, 100.0),
+    ];
+    generate::edge_list(LiteNetworkBuilder::new(), &locations, &edges)
+}
+
+#[test]
+fn edge_list_paths_take_the_shortest_detour() {
+    let network = detour_network();
+
+    let known = [
+        (0, 3, Some(3000)),
+        (3, 0, Some(3000)),
+        (3, 2, Some(5000)),
+        (4, 3, Some(4000)),
+        (0, 4, None),
+        (0, 5, None),
+        (5, 0, None),
+        (2, 2, Some(0)),
+    ];
+    for (source, target, expected) in known {
+        check_paths(&network, NodeId::new(source), NodeId::new(target), expected);
+    }
+}
+
+/// Two roads of 1.9 metres together are longer than one of 3.5, but not when every edge is cut
+/// to whole metres.
+#[test]
+fn fractional_metres_are_not_truncated() {
+    let locations = [(0.0, 0.0), (1.5, 1.0), (3.0, 0.0)];
+    let edges = [(0, 1, 1.9), (1, 2, 1.9), (0, 2, 3.5)];
+    let network = generate::edge_list(LiteNetworkBuilder::new(), &locations, &edges);
+    check_paths(&network, NodeId(0), NodeId(1), Some(19));
+    check_paths(&network, NodeId(0), NodeId(2), Some(35));
+
+    let costs =
+        DijkstraIterator::with_metric(&network, Metres, NodeId(0), DijkstraDirection::Forward)
+            .map(|(cost, node)| (node.index(), cost))
+            .collect::<Vec<_>>();
+    assert_eq!(
+        costs,
+        vec![
+            (0, F32Wrapper(0.0)),
+            (1, F32Wrapper(1.9)),
+            (2, F32Wrapper(3.5))
+        ]
+    );
+}
+
+/// The chain from 1 to 5 is a straight line of edges that are all rounded down, so together they
+/// cost less than the straight line from 1 to 6. The direct road from 0 to 5 is a decimetre
+/// longer.
+#[test]
+fn a_star_bound_allows_for_rounded_edges() {
+    let locations = [
+        (0.0, 5.0),
+        (0.0, 0.0),
+        (10.04, 0.0),
+        (20.08, 0.0),
+        (30.12, 0.0),
+        (40.16, 0.0),
+        (41.205, 0.0),
+    ];
+    let edges = [
+        (0, 1, 5.0),
+        (1, 2, 10.04),
+        (2, 3, 10.04),
+        (3, 4, 10.04),
+        (4, 5, 10.04),
+        (5, 6, 1.045),
+        (0, 5, 45.1),
+    ];
+    let network = generate::edge_list(LiteNetworkBuilder::new(), &locations, &edges);
+    check_paths(&network, NodeId(0), NodeId(6), Some(460));
+
+    let a_star = SimpleAStar::new(network);
+    assert_eq!(
+        a_star.path(NodeId(0), NodeId(6)),
+        Ok((460, (0..6).map(EdgeId).collect()))
+    );
+}
+
+/// The motorway from 0 to 2 is longer than the road through 1, but faster.
+#[test]
+fn travel_time_prefers_fast_roads() {
+    let mut builder = LiteNetworkBuilder::new();
+    let nodes = [0.0, 1000.0, 2000.0].map(|x| NodeCoord { x, y: 0.0 });
+    let [start, middle, end] =
+        [0, 1, 2].map(|node| builder.add_junction(node, nodes[node as usize]));
+    let town = vec![
+        builder.add_edge(0, start, middle, 1000.0, 50, 1).unwrap(),
+        builder.add_edge(1, middle, end, 1000.0, 50, 1).unwrap(),
+    ];
+    let motorway = vec![builder.add_edge(2, start, end, 2500.0, 100, 2).unwrap()];
+    let network = builder.build();
+
+    let dijkstra = DijkstraPathAlgorithm::new(network.clone());
+    let bidirectional = BiDirDijkstraPathAlgorithm::new(network.clone());
+    let a_star = SimpleAStar::new(network);
+
+    // 2000 metres at 50 km/h take 144 seconds, 2500 metres at 100 km/h 90 seconds.
+    let fastest = Ok((90_000, motorway));
+    assert_eq!(dijkstra.path_with(TravelTime, start, end), fastest);
+    assert_eq!(bidirectional.path_with(TravelTime, start, end), fastest);
+    assert_eq!(a_star.path_with(TravelTime, start, end), fastest);
+
+    let shortest = Ok((20_000, town));
+    assert_eq!(PathAlgorithm::path(&dijkstra, start, end), shortest);
+    assert_eq!(PathAlgorithm::path(&bidirectional, start, end), shortest);
+    assert_eq!(a_star.path(start, end), shortest);
+}
+
+#[test]
+fn verify_accepts_algorithms_that_agree() {
+    let network = generate::random_geometric(LiteNetworkBuilder::new(), 80, 1000.0, 200.0, 7);
+    let dijkstra = DijkstraPathAlgorithm::new(network.clone());
+    let bidirectional = BiDirDijkstraPathAlgorithm::new(network.clone());
+    let a_star = SimpleAStar::new(network.clone());
+
+    let dijkstra = |s, t| PathAlgorithm::path(&dijkstra, s, t);
+    let bidirectional = |s, t| PathAlgorithm::path(&bidirectional, s, t);
+    let a_star = |s, t| a_star.path(s, t);
+    let algorithms: [(&str, &PathFn); 3] = [
+        ("dijkstra", &dijkstra),
+        ("bidirectional", &bidirectional),
+        ("a-star", &a_star),
+    ];
+
+    let pairs = verify::random_pairs(&network, 100, 3);
+    assert_eq!(
+        verify::verify(&network, Decimetres, &algorithms, &pairs),
+        Vec::new()
+    );
+}
+
+#[test]
+fn verify_reports_every_broken_path() {
+    let network = detour_network();
+    let dijkstra = DijkstraPathAlgorithm::new(network.clone());
+    let dijkstra = |s, t| PathAlgorithm::path(&dijkstra, s, t);
+    let direct = |_, _| Ok((3500, vec![EdgeId(3)]));
+    let none = |_, _| Err(());
+    let reversed = |s, t| {
+        dijkstra(s, t).map(|(cost, mut edges)| {
+            edges.reverse();
+            (cost, edges)
+        })
+    };
+    let misreported = |s, t| dijkstra(s, t).map(|(cost, edges)| (cost + 1, edges));
+
+    let mismatch = |algorithm: &str, source, target, problem| Mismatch {
+        algorithm: algorithm.to_string(),
+        source: NodeId(source),
+        target: NodeId(target),
+        problem,
+    };
+    let pairs = [(0, 3), (0, 4), (3, 2)].map(|(s, t)| (NodeId(s), NodeId(t)));
+
+    let algorithms: [(&str, &PathFn); 5] = [
+        ("dijkstra", &dijkstra),
+        ("direct", &direct),
+        ("none", &none),
+        ("reversed", &reversed),
+        ("misreported", &misreported),
+    ];
+    let misreported_cost = |path| Problem::ReportedCost {
+        reported: path + 1,
+        path,
+    };
+    let wrong_ends = Problem::WrongEnds {
+        start: NodeId(0),
+        end: NodeId(3),
+    };
+    assert_eq!(
+        verify::verify(&network, Decimetres, &algorithms, &pairs),
+        vec![
+            mismatch("reversed", 0, 3, Problem::NotConnected(1)),
+            mismatch("misreported", 0, 3, misreported_cost(3000)),
+            mismatch(
+                "direct",
+                0,
+                3,
+                Problem::Cost {
+                    expected: 3000,
+                    found: 3500
+                }
+            ),
+            mismatch("none", 0, 3, Problem::MissingPath),
+            mismatch("direct", 0, 4, wrong_ends.clone()),
+            mismatch("direct", 3, 2, wrong_ends),
+            mismatch("reversed", 3, 2, Problem::NotConnected(1)),
+            mismatch("misreported", 3, 2, misreported_cost(5000)),
+            mismatch("none", 3, 2, Problem::MissingPath),
+        ]
+    );
+
+    // Nothing is compared with a reference that is broken itself.
+    let algorithms: [(&str, &PathFn); 2] = [("reversed", &reversed), ("dijkstra", &dijkstra)];
+    assert_eq!(
+        verify::verify(&network, Decimetres, &algorithms, &pairs[..1]),
+        vec![mismatch("reversed", 0, 3, Problem::NotConnected(1))]
+    );
+    let algorithms: [(&str, &PathFn); 2] = [("none", &none), ("dijkstra", &dijkstra)];
+    assert_eq!(
+        verify::verify(&network, Decimetres, &algorithms, &pairs[..1]),
+        vec![mismatch("dijkstra", 0, 3, Problem::UnexpectedPath)]
+    );
+}
+
+#[test]
+fn bidirectional_many_to_many_skips_unreachable_pairs() {
+    let network = detour_network();
+    let bidirectional = BiDirDijkstraPathAlgorithm::new(network.clone());
+    let nodes = [0, 3, 4, 5].map(NodeId::new);
+
+    let paths = match ManyToManyAlgorithm::path(&bidirectional, &nodes) {
+        Err(ManyManyErrors::NotAllPairsFound(paths)) => paths,
+        result => panic!("expected some unreachable pairs, got {:?}", result),
+    };
+
+    let mut found = paths
+        .iter()
+        .map(|path| {
+            let length = path_length(&network, path.source, &path.edges).unwrap();
+            (path.source.index(), path.target.index(), length)
+        })
+        .collect::<Vec<_>>();
+    found.sort_unstable();
+    assert_eq!(
+        found,
+        vec![(0, 3, 3000), (3, 0, 3000), (4, 0, 1000), (4, 3, 4000)]
+    );
+}
+
+/// Networks for the algorithms with preprocessing: a grid, a random geometric network and copies
+/// of it where many roads are one-way and some roads are longer.
+fn preprocessed_networks() -> Vec<LiteNetwork> {
+    let geometric = generate::random_geometric(LiteNetworkBuilder::new(), 300, 2000.0, 200.0, 5);
+    vec![
+        generate::grid(LiteNetworkBuilder::new(), 15, 12, 100.0),
+        directed(&geometric, 1),
+        directed(&geometric, 2),
+        geometric,
+    ]
+}
+
+/// A copy of `network` without a quarter of its edges, and with a third of the others three
+/// times as long, so many roads are one-way and some nodes cannot reach each other.
+fn directed(network: &LiteNetwork, seed: u64) -> LiteNetwork {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let locations = (0..network.nodes_len())
+        .map(|node| network.node_location(NodeId::new(node)))
+        .map(|location| (location.x, location.y))
+        .collect::<Vec<_>>();
+    let edges = (0..network.edge_len())
+        .map(EdgeId::new)
+        .filter_map(|edge| {
+            if rng.gen_bool(0.25) {
+                return None;
+            }
+            let factor = if rng.gen_bool(1.0 / 3.0) { 3.0 } else { 1.0 };
+            Some((
+                network.edge_source(edge).index(),
+                network.edge_target(edge).index(),
+                network.edge_distance(edge) * factor,
+            ))
+        })
+        .collect::<Vec<_>>();
+    generate::edge_list(LiteNetworkBuilder::new(), &locations, &edges)
+}
+
+/// Small neighbourhoods, so the small networks get several levels.
+fn highway_parameters() -> HighwayParameters {
+    HighwayParameters {
+        neighbourhood_size: 5,
+        max_levels: 4,
+    }
+}
+
+fn highway_overlay(network: &LiteNetwork) -> MultiLevelOverlay {
+    let hierarchy = HighwayHierarchy::build(network, highway_parameters());
+    let (ranking, level_sizes) =
+        HighwayNodeRoutingPathAlgorithm::hierarchy_ranking(network, &hierarchy);
+    MultiLevelOverlay::build(network.clone(), &ranking, &level_sizes).unwrap()
+}
+
+/// Checks that `path` finds the paths that Dijkstra finds for random pairs of nodes, with the same
+/// cost, and no path where Dijkstra finds none.
+fn check_with_dijkstra<F>(network: &LiteNetwork, path: F, seed: u64)
+where
+    F: Fn(NodeId, NodeId) -> Result<(u64, Vec<EdgeId>), ()>,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..300 {
+        let source = NodeId::new(rng.gen_range(0..network.nodes_len()));
+        let target = NodeId::new(rng.gen_range(0..network.nodes_len()));
+        let expected = network
+            .forward_dijkstra(source)
+            .find(|(_, node)| *node == target)
+            .map(|(cost, _)| cost);
+
+        let context = format!("from {:?} to {:?}", source, target);
+        match (expected, path(source, target)) {
+            (None, Err(())) => {}
+            (Some(expected), Ok((cost, edges))) => {
+                assert_eq!(cost, expected, "{}", context);
+                assert_eq!(
+                    path_length(network, source, &edges),
+                    Some(expected),
+                    "{}",
+                    context
+                );
+                let end = edges
+                    .last()
+                    .map_or(source, |edge| network.edge_target(*edge));
+                assert_eq!(end, target, "{}", context);
+            }
+            (expected, found) => panic!("{}: expected {:?}, found {:?}", context, expected, found),
+        }
+    }
+}
+
+/// Checks that the many-to-many paths of `algorithm` between some nodes are paths that cost as
+/// much as the paths of Dijkstra, and that only pairs without a path are missing.
+fn check_many_to_many<A: ManyToManyAlgorithm<Network = LiteNetwork>>(algorithm: &A) {
+    let network = algorithm.network();
+    let nodes = (0..network.nodes_len())
+        .step_by(network.nodes_len() / 12)
+        .map(NodeId::new)
+        .collect::<Vec<_>>();
+
+    let mut expected = HashMap::new();
+    for source in &nodes {
+        let costs = network.forward_dijkstra(*source).collect::<Vec<_>>();
+        for (cost, target) in costs {
+            if target != *source && nodes.contains(&target) {
+                expected.insert((*source, target), cost);
+            }
+        }
+    }
+
+    let paths = match algorithm.path(&nodes) {
+        Ok(paths) | Err(ManyManyErrors::NotAllPairsFound(paths)) => paths,
+        Err(ManyManyErrors::EmptyNodeList) => panic!("there are nodes"),
+    };
+    let found = paths
+        .iter()
+        .map(
+            |EdgePath {
+                 source,
+                 target,
+                 edges,
+             }| {
+                let end = edges
+                    .last()
+                    .map_or(*source, |edge| network.edge_target(*edge));
+                assert_eq!(end, *target);
+                let length = path_length(network, *source, edges).expect("path is not connected");
+                ((*source, *target), length)
+            },
+        )
+        .collect::<HashMap<_, _>>();
+    assert_eq!(found.len(), paths.len());
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn contraction_hierarchy_matches_dijkstra() {
+    for (seed, network) in (0..).zip(preprocessed_networks()) {
+        let hierarchy = ContractionHierarchy::build(network.clone()).unwrap();
+        assert_ne!(hierarchy.shortcuts().len(), 0);
+        let algorithm = ContractionHierarchyPathAlgorithm::with_hierarchy(hierarchy);
+
+        check_with_dijkstra(&network, |s, t| PathAlgorithm::path(&algorithm, s, t), seed);
+        check_many_to_many(&algorithm);
+    }
+}
+
+#[test]
+fn highway_hierarchy_matches_dijkstra() {
+    for (seed, network) in (0..).zip(preprocessed_networks()) {
+        let hierarchy = HighwayHierarchy::build(&network, highway_parameters());
+        assert!(hierarchy.top_level() > 1);
+        let algorithm = HighwayHierarchyPathAlgorithm::with_hierarchy(network.clone(), hierarchy);
+
+        check_with_dijkstra(&network, |s, t| PathAlgorithm::path(&algorithm, s, t), seed);
+        check_many_to_many(&algorithm);
+    }
+}
+
+#[test]
+fn highway_node_routing_matches_dijkstra() {
+    for (seed, network) in (0..).zip(preprocessed_networks()) {
+        let overlay = highway_overlay(&network);
+        assert!(overlay.top_level() > 1);
+        let algorithm = HighwayNodeRoutingPathAlgorithm::with_overlay(overlay);
+
+        check_with_dijkstra(&network, |s, t| PathAlgorithm::path(&algorithm, s, t), seed);
+        check_many_to_many(&algorithm);
+    }
+}
+
+#[test]
+fn highway_node_routing_matches_dijkstra_after_updates() {
+    for (seed, mut network) in (0..).zip(preprocessed_networks()) {
+        let mut algorithm =
+            HighwayNodeRoutingPathAlgorithm::with_overlay(highway_overlay(&network));
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        // One edge first, then many, some longer and some shorter.
+        for changed in [1, 40] {
+            let changes = (0..changed)
+                .map(|_| {
+                    let edge = EdgeId::new(rng.gen_range(0..network.edge_len()));
+                    let distance = network.edge_distance(edge);
+                    let factor = if rng.gen_bool(0.5) { 3.0 } else { 0.25 };
+                    (edge, (distance * factor).round().max(1.0))
+                })
+                .collect::<Vec<_>>();
+            for (edge, distance) in &changes {
+                network.set_edge_distance(*edge, *distance).unwrap();
+            }
+            algorithm.update_edge_distances(&changes).unwrap();
+
+            check_with_dijkstra(
+                &network,
+                |s, t| PathAlgorithm::path(&algorithm, s, t),
+                seed * 10 + changed,
+            );
+        }
+        check_many_to_many(&algorithm);
+    }
+}
+
+#[test]
+fn regions_match_dijkstra() {
+    for (seed, network) in (0..).zip(preprocessed_networks()) {
+        let directory =
+            std::env::temp_dir().join(format!("dutchroad_{}_regions_{}", std::process::id(), seed));
+        // Cells small enough that most paths cross several of them.
+        let parameters = RegionParameters {
+            cell_size: 300.0,
+            growth: 2.0,
+        };
+        region::write_regions(&highway_overlay(&network), parameters, &directory).unwrap();
+        let algorithm = RegionPathAlgorithm::with_network(RegionNetwork::open(&directory).unwrap());
+        let regions = algorithm.network();
+        assert!(regions.cells_len() > 1);
+        assert_eq!(regions.loaded_cells(), 0);
+
+        let path = |s: NodeId, t: NodeId| {
+            let location = |node| network.node_location(node);
+            let source = regions.find_node(s, location(s)).unwrap().ok_or(())?;
+            let target = regions.find_node(t, location(t)).unwrap().ok_or(())?;
+            let (cost, edges) = algorithm.path(source, target)?;
+            let edges = edges
+                .iter()
+                .flat_map(|edge| regions.original_edges(*edge).unwrap().to_vec())
+                .collect();
+            Ok((cost, edges))
+        };
+
+        // A query between opposite corners only loads the cells its searches reach.
+        let _ = path(NodeId::new(0), NodeId::new(network.nodes_len() - 1));
+        let loaded = regions.loaded_cells();
+        assert!(
+            loaded > 1 && loaded < regions.cells_len(),
+            "loaded {} of {} cells",
+            loaded,
+            regions.cells_len()
+        );
+
+        check_with_dijkstra(&network, path, seed);
+        regions.check_loaded_cells().unwrap();
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
+
+/// Cells are only read when they are used, a cell that is removed or replaced by a cell of other
+/// regions after opening is an error of the query that needs it.
+#[test]
+fn regions_report_changed_cells() {
+    let network = generate::grid(LiteNetworkBuilder::new(), 12, 12, 100.0);
+    let overlay = highway_overlay(&network);
+    let directory =
+        |name| std::env::temp_dir().join(format!("dutchroad_{}_{}", std::process::id(), name));
+    let (changed, other) = (directory("changed_regions"), directory("other_regions"));
+    let parameters = RegionParameters {
+        cell_size: 300.0,
+        growth: 2.0,
+    };
+    region::write_regions(&overlay, parameters, &changed).unwrap();
+    region::write_regions(&overlay, parameters, &other).unwrap();
+
+    let cell_file = |directory: &Path, cell| directory.join(format!("cell_{}.axe", cell));
+    fs::remove_file(cell_file(&changed, 0)).unwrap();
+    let regions = RegionNetwork::open(&changed).unwrap();
+    let cells = regions.cells_len();
+    assert!(cells > 2);
+    fs::copy(cell_file(&other, 1), cell_file(&changed, 1)).unwrap();
+    fs::copy(cell_file(&other, 2), cell_file(&changed, 3)).unwrap();
+
+    let algorithm = RegionPathAlgorithm::with_network(regions);
+    let regions = algorithm.network();
+    let mut failed = 0;
+    for node in (0..network.nodes_len()).map(NodeId::new) {
+        match regions.find_node(node, network.node_location(node)) {
+            Ok(found) => assert!(found.is_some()),
+            Err(_) => failed += 1,
+        }
+    }
+    assert_ne!(failed, 0);
+    assert!(regions.check_loaded_cells().is_err());
+    assert!(algorithm.path(NodeId::new(0), NodeId::new(1)).is_err());
+    assert!(regions.loaded_cells() <= cells - 3);
+
+    fs::remove_dir_all(changed).unwrap();
+    fs::remove_dir_all(other).unwrap();
+}
+
+/// Generated distances are fractional metres, which every algorithm must add up exactly.
+#[test]
+fn verify_accepts_every_algorithm_on_fractional_metres() {
+    let network = generate::random_geometric(LiteNetworkBuilder::new(), 200, 1500.0, 200.0, 11);
+    assert!((0..network.edge_len())
+        .map(EdgeId::new)
+        .any(|edge| network.edge_decimetres(edge) % 10 != 0));
+
+    let dijkstra = DijkstraPathAlgorithm::new(network.clone());
+    let bidirectional = BiDirDijkstraPathAlgorithm::new(network.clone());
+    let a_star = SimpleAStar::new(network.clone());
+    let ch = ContractionHierarchyPathAlgorithm::with_hierarchy(
+        ContractionHierarchy::build(network.clone()).unwrap(),
+    );
+    let hh = HighwayHierarchyPathAlgorithm::with_hierarchy(
+        network.clone(),
+        HighwayHierarchy::build(&network, highway_parameters()),
+    );
+    let hnr = HighwayNodeRoutingPathAlgorithm::with_overlay(highway_overlay(&network));
+
+    let dijkstra = |s, t| PathAlgorithm::path(&dijkstra, s, t);
+    let bidirectional = |s, t| PathAlgorithm::path(&bidirectional, s, t);
+    let a_star = |s, t| a_star.path(s, t);
+    let ch = |s, t| PathAlgorithm::path(&ch, s, t);
+    let hh = |s, t| PathAlgorithm::path(&hh, s, t);
+    let hnr = |s, t| PathAlgorithm::path(&hnr, s, t);
+    let algorithms: [(&str, &PathFn); 6] = [
+        ("dijkstra", &dijkstra),
+        ("bidirectional", &bidirectional),
+        ("a-star", &a_star),
+        ("ch", &ch),
+        ("hh", &hh),
+        ("hnr", &hnr),
+    ];
+
+    let pairs = verify::random_pairs(&network, 300, 5);
+    assert_eq!(
+        verify::verify(&network, Decimetres, &algorithms, &pairs),
+        Vec::new()
+    );
+}
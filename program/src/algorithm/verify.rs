@@ -0,0 +1,173 @@
+// Checks that algorithms agree on the same queries. Every path must be connected and go from the
+// source to the target, the cost an algorithm reports must be the cost of its path and must be the
+// same as the cost of the first algorithm, which is the reference. Costs are integers, such as
+// whole decimetres, so paths of the same length compare equal whatever edges they take.
+use super::weight::{self, Metric};
+use crate::network::{EdgeId, Network, NodeId};
+use rand::{distributions::Uniform, rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::fmt;
+
+/// Finds the cost and the edges of a path between two nodes of the network that is verified.
+pub type PathFn<'a> = dyn Fn(NodeId, NodeId) -> Result<(u64, Vec<EdgeId>), ()> + Sync + 'a;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// Found a path where the reference found none.
+    UnexpectedPath,
+    /// Found no path where the reference found one.
+    MissingPath,
+    /// The edge at this position does not start where the previous one ended.
+    NotConnected(usize),
+    /// The path starts or ends at another node than was asked for.
+    WrongEnds { start: NodeId, end: NodeId },
+    /// The algorithm reports another cost than its path has.
+    ReportedCost { reported: u64, path: u64 },
+    /// The path costs more or less than the path of the reference.
+    Cost { expected: u64, found: u64 },
+}
+
+/// A problem with the path of one algorithm between one pair of nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub algorithm: String,
+    pub source: NodeId,
+    pub target: NodeId,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::UnexpectedPath => write!(f, "found a path, the reference found none"),
+            Problem::MissingPath => write!(f, "found no path, the reference found one"),
+            Problem::NotConnected(position) => write!(f, "edge {} is not connected", position),
+            Problem::WrongEnds { start, end } => {
+                write!(f, "path goes from {:?} to {:?}", start, end)
+            }
+            Problem::ReportedCost { reported, path } => {
+                write!(f, "reports cost {}, the path costs {}", reported, path)
+            }
+            Problem::Cost { expected, found } => {
+                write!(f, "cost {}, the reference costs {}", found, expected)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} from {:?} to {:?}: {}",
+            self.algorithm, self.source, self.target, self.problem
+        )
+    }
+}
+
+/// `amount` source and target pairs of random nodes.
+pub fn random_pairs<S: Network>(network: &S, amount: usize, seed: u64) -> Vec<(NodeId, NodeId)> {
+    let nodes = Uniform::new(0, network.nodes_len());
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..amount)
+        .map(|_| {
+            let source = NodeId::new(rng.sample(nodes));
+            let target = NodeId::new(rng.sample(nodes));
+            (source, target)
+        })
+        .collect()
+}
+
+/// Runs every algorithm on every pair and returns all mismatches, in the order of the pairs. The
+/// first algorithm is the reference, when its own path is wrong the pair is not compared. Paths
+/// cost their weight by `metric`, which is what the algorithms must report.
+pub fn verify<S, M>(
+    network: &S,
+    metric: M,
+    algorithms: &[(&str, &PathFn)],
+    pairs: &[(NodeId, NodeId)],
+) -> Vec<Mismatch>
+where
+    S: Network + Sync,
+    M: Metric<Weight = u64>,
+{
+    pairs
+        .par_iter()
+        .flat_map_iter(|(source, target)| {
+            let mismatch = |algorithm: &str, problem| Mismatch {
+                algorithm: algorithm.to_string(),
+                source: *source,
+                target: *target,
+                problem,
+            };
+
+            // The cost of every path, or `None` when there is no path.
+            let costs = algorithms
+                .iter()
+                .map(|(_, path)| match path(*source, *target) {
+                    Ok((cost, edges)) => {
+                        check_path(network, &metric, *source, *target, cost, &edges).map(Some)
+                    }
+                    Err(()) => Ok(None),
+                })
+                .collect::<Vec<_>>();
+
+            let mut mismatches = Vec::new();
+            for ((name, _), cost) in algorithms.iter().zip(&costs) {
+                if let Err(problem) = cost {
+                    mismatches.push(mismatch(name, problem.clone()));
+                }
+            }
+
+            let reference = match costs.first() {
+                Some(Ok(reference)) => *reference,
+                _ => return mismatches,
+            };
+            for ((name, _), cost) in algorithms.iter().zip(&costs).skip(1) {
+                let problem = match (reference, cost) {
+                    (Some(expected), Ok(Some(found))) if expected != *found => Problem::Cost {
+                        expected,
+                        found: *found,
+                    },
+                    (Some(_), Ok(None)) => Problem::MissingPath,
+                    (None, Ok(Some(_))) => Problem::UnexpectedPath,
+                    _ => continue,
+                };
+                mismatches.push(mismatch(name, problem));
+            }
+            mismatches
+        })
+        .collect()
+}
+
+/// The reported cost of a path that is connected, goes from `source` to `target` and has that cost.
+fn check_path<S: Network, M: Metric<Weight = u64>>(
+    network: &S,
+    metric: M,
+    source: NodeId,
+    target: NodeId,
+    reported: u64,
+    edges: &[EdgeId],
+) -> Result<u64, Problem> {
+    for (position, pair) in edges.windows(2).enumerate() {
+        if network.edge_target(pair[0]) != network.edge_source(pair[1]) {
+            return Err(Problem::NotConnected(position + 1));
+        }
+    }
+
+    let start = edges
+        .first()
+        .map_or(source, |edge| network.edge_source(*edge));
+    let end = edges
+        .last()
+        .map_or(source, |edge| network.edge_target(*edge));
+    if start != source || end != target {
+        return Err(Problem::WrongEnds { start, end });
+    }
+
+    let path = weight::path_weight(network, metric, edges);
+    if reported != path {
+        return Err(Problem::ReportedCost { reported, path });
+    }
+    Ok(reported)
+}
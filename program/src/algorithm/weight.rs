@@ -0,0 +1,133 @@
+// What the searches minimise. A metric gives every edge a weight and a search adds the weights of
+// a path up. Integer weights add up exactly in any order, so every algorithm reports the same cost
+// for the same path. `Decimetres` is the metric of the preprocessed hierarchies.
+pub use crate::f32_wrapper::F32Wrapper;
+use crate::network::{EdgeId, Network, DECIMETRES_PER_METRE};
+use std::{fmt::Debug, ops::Add};
+
+/// The cost of an edge or a path.
+pub trait Weight: Copy + Ord + Add<Output = Self> + Debug + Send + Sync {
+    const ZERO: Self;
+
+    fn to_f64(self) -> f64;
+
+    /// The largest weight that is at most `value`, which is not negative.
+    fn floor(value: f64) -> Self;
+}
+
+impl Weight for u64 {
+    const ZERO: Self = 0;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn floor(value: f64) -> Self {
+        value.floor() as u64
+    }
+}
+
+impl Weight for F32Wrapper {
+    const ZERO: Self = F32Wrapper(0.0);
+
+    fn to_f64(self) -> f64 {
+        f64::from(self.0)
+    }
+
+    fn floor(value: f64) -> Self {
+        let weight = value as f32;
+        if f64::from(weight) > value {
+            // Rounded up to the nearest f32, take the one below it.
+            F32Wrapper(f32::from_bits(weight.to_bits() - 1))
+        } else {
+            F32Wrapper(weight)
+        }
+    }
+}
+
+pub trait Metric: Sync {
+    type Weight: Weight;
+
+    fn weight<N: Network>(&self, network: &N, edge: EdgeId) -> Self::Weight;
+
+    /// The weight of a straight line of `metres` before it is rounded to the unit of the weights.
+    /// No road is shorter than the straight line between its ends, but its rounded weight can be
+    /// less than this.
+    fn straight_line(&self, metres: f64) -> f64;
+}
+
+impl<M: Metric> Metric for &M {
+    type Weight = M::Weight;
+
+    fn weight<N: Network>(&self, network: &N, edge: EdgeId) -> Self::Weight {
+        (*self).weight(network, edge)
+    }
+
+    fn straight_line(&self, metres: f64) -> f64 {
+        (*self).straight_line(metres)
+    }
+}
+
+/// Length in whole decimetres, as `LiteNetwork` stores it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Decimetres;
+
+impl Metric for Decimetres {
+    type Weight = u64;
+
+    fn weight<N: Network>(&self, network: &N, edge: EdgeId) -> u64 {
+        u64::from(network.edge_decimetres(edge))
+    }
+
+    fn straight_line(&self, metres: f64) -> f64 {
+        metres * f64::from(DECIMETRES_PER_METRE)
+    }
+}
+
+/// Time to drive an edge at its speed, in whole milliseconds. Edges without a speed count as
+/// 1 km/h, so they are avoided. A straight line is driven at the highest speed an edge can have.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TravelTime;
+
+impl Metric for TravelTime {
+    type Weight = u64;
+
+    fn weight<N: Network>(&self, network: &N, edge: EdgeId) -> u64 {
+        let decimetres = u64::from(network.edge_decimetres(edge));
+        let speed = u64::from(network.edge_speed(edge).max(1));
+        // Rounded to the nearest millisecond.
+        (decimetres * MILLISECONDS_PER_DECIMETRE_AT_1_KMH + speed / 2) / speed
+    }
+
+    fn straight_line(&self, metres: f64) -> f64 {
+        let decimetres = Decimetres.straight_line(metres);
+        decimetres * MILLISECONDS_PER_DECIMETRE_AT_1_KMH as f64 / f64::from(u8::MAX)
+    }
+}
+
+/// Length in metres as a float. Sums are rounded, so paths of the same length can cost slightly
+/// differently depending on the order of their edges.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Metres;
+
+impl Metric for Metres {
+    type Weight = F32Wrapper;
+
+    fn weight<N: Network>(&self, network: &N, edge: EdgeId) -> F32Wrapper {
+        F32Wrapper(network.edge_distance(edge))
+    }
+
+    fn straight_line(&self, metres: f64) -> f64 {
+        metres
+    }
+}
+
+// 1 km/h is 10,000 decimetres per 3,600,000 milliseconds.
+const MILLISECONDS_PER_DECIMETRE_AT_1_KMH: u64 = 360;
+
+/// The cost of a path of edges.
+pub fn path_weight<N: Network, M: Metric>(network: &N, metric: M, edges: &[EdgeId]) -> M::Weight {
+    edges.iter().fold(M::Weight::ZERO, |sum, edge| {
+        sum + metric.weight(network, *edge)
+    })
+}
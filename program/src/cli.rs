@@ -0,0 +1,894 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use dutchroad::{
+    algorithm::{
+        bidirectional::bidirectional_dijkstra,
+        contraction_hierarchy::ContractionHierarchyPathAlgorithm,
+        dijkstra::{dijkstra_path, DijkstraPathAlgorithm},
+        dijkstra_bi_dir::BiDirDijkstraPathAlgorithm,
+        highway_hierarchy::HighwayHierarchyPathAlgorithm,
+        highway_node_routing::HighwayNodeRoutingPathAlgorithm,
+        pairwise_paths,
+        simple_a_star::{a_star_path, SimpleAStar, StraightLineBound},
+        verify::{self, PathFn},
+        weight::{self, Decimetres, TravelTime},
+        EdgePath, ManyManyErrors, ManyToManyAlgorithm, PathAlgorithm,
+    },
+    contraction::{hierarchy::ContractionHierarchy, CoreNetwork},
+    highway::{HighwayHierarchy, HighwayParameters},
+    network::{
+        builder::DistanceError,
+        closest_node,
+        file::{self, NetworkFile, Source, SourceCheck},
+        reorder::NodeOrder,
+        speed::SpeedTable,
+        EdgeId, LiteNetwork, MappedNetwork, Network, NodeCoord, NodeId, Writeable,
+    },
+    node_routing::MultiLevelOverlay,
+    preprocess::{self, BuildOptions, Preprocessed, DEFAULT_NETWORK, DEFAULT_SHAPEFILE},
+};
+use rand::{distributions::Uniform, prelude::StdRng, Rng, SeedableRng};
+use std::{
+    convert::Infallible,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "dutchroad", about = "Routing on the Dutch road network (NWB)")]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Build the network from a shapefile, and the preprocessing of the algorithm if it has any.
+    Preprocess {
+        #[command(flatten)]
+        files: NetworkFiles,
+        #[arg(long, value_enum, default_value_t = Algorithm::Dijkstra)]
+        algorithm: Algorithm,
+        /// Also write the network as a memory-mapped file next to it, with the extension `map`.
+        #[arg(long)]
+        mapped: bool,
+    },
+    /// Find the shortest path between two nodes.
+    Query {
+        #[command(flatten)]
+        files: NetworkFiles,
+        #[arg(long, value_enum, default_value_t = Algorithm::Dijkstra)]
+        algorithm: Algorithm,
+        #[arg(long, value_enum, default_value_t = Metric::Distance)]
+        metric: Metric,
+        /// A junction id or a coordinate `x,y` (Rijksdriehoek).
+        #[arg(long)]
+        from: NodeSelector,
+        /// A junction id or a coordinate `x,y` (Rijksdriehoek).
+        #[arg(long)]
+        to: NodeSelector,
+        /// Print the wegvak ids of the roads on the path, followed by their street name, road
+        /// number, municipality and road type separated by tabs.
+        #[arg(long)]
+        roads: bool,
+        /// Search the memory-mapped network written by `preprocess --mapped`, which opens
+        /// without reading the distances and metadata of the roads. Only dijkstra, bidirectional
+        /// and a-star run on it.
+        #[arg(long)]
+        mapped: bool,
+    },
+    /// Compute the distances, or travel times in seconds, between all pairs of nodes as a csv
+    /// table.
+    Matrix {
+        #[command(flatten)]
+        files: NetworkFiles,
+        #[arg(long, value_enum, default_value_t = Algorithm::Dijkstra)]
+        algorithm: Algorithm,
+        #[arg(long, value_enum, default_value_t = Metric::Distance)]
+        metric: Metric,
+        /// File with a junction id or a coordinate `x,y` on every line.
+        #[arg(long, conflicts_with = "random", required_unless_present = "random")]
+        nodes: Option<PathBuf>,
+        /// Use this many random nodes instead of a node file.
+        #[arg(long)]
+        random: Option<usize>,
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        /// Write the table to this file instead of stdout.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Print statistics of the network, and of the preprocessing of the algorithm if it has any.
+    Stats {
+        #[command(flatten)]
+        files: NetworkFiles,
+        #[arg(long, value_enum, default_value_t = Algorithm::Dijkstra)]
+        algorithm: Algorithm,
+        /// Also bypass the nodes in the middle of roads and print the size of the core network
+        /// that is left.
+        #[arg(long)]
+        core: bool,
+    },
+    /// Run algorithms on random pairs of nodes and report every path that is broken, that costs
+    /// something else than the algorithm reports or than the path of the first algorithm.
+    Verify {
+        #[command(flatten)]
+        files: NetworkFiles,
+        /// The algorithms to compare, all that support the metric when none are given.
+        #[arg(long, value_enum, value_delimiter = ',')]
+        algorithms: Vec<Algorithm>,
+        #[arg(long, value_enum, default_value_t = Metric::Distance)]
+        metric: Metric,
+        #[arg(long, default_value_t = 100)]
+        pairs: usize,
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+    },
+}
+
+#[derive(Debug, Args)]
+struct NetworkFiles {
+    /// The NWB Wegvakken shapefile, only read by other commands when the network does not exist.
+    #[arg(long, default_value = DEFAULT_SHAPEFILE)]
+    shapefile: PathBuf,
+    /// The preprocessed network.
+    #[arg(long, default_value = DEFAULT_NETWORK)]
+    network: PathBuf,
+    /// Renumber the nodes of the network, so nodes that are close are also close in memory.
+    #[arg(long, value_enum)]
+    reorder: Option<Reorder>,
+    /// Speed table that gives roads their speed by the fields of their record, one rule
+    /// `FIELD=VALUE .. km/h` per line. A built-in table is used when none is given.
+    #[arg(long)]
+    speeds: Option<PathBuf>,
+    /// Maximum speeds shapefile of the wegkenmerken (weggeg), its speeds are used instead of the
+    /// speed table where they are known.
+    #[arg(long)]
+    max_speeds: Option<PathBuf>,
+    /// Lanes shapefile of the wegkenmerken (weggeg).
+    #[arg(long)]
+    lanes: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Reorder {
+    Hilbert,
+    BreadthFirst,
+}
+
+impl NetworkFiles {
+    fn options(&self) -> Result<BuildOptions, Box<dyn Error>> {
+        Ok(BuildOptions {
+            reorder: self.reorder.map(|reorder| match reorder {
+                Reorder::Hilbert => NodeOrder::Hilbert,
+                Reorder::BreadthFirst => NodeOrder::BreadthFirst,
+            }),
+            speeds: match &self.speeds {
+                Some(file) => SpeedTable::read(file)?,
+                None => SpeedTable::default(),
+            },
+            max_speeds: self.max_speeds.clone(),
+            lanes: self.lanes.clone(),
+        })
+    }
+
+    /// Reads the network, or builds it when it does not exist or is stale.
+    fn load(&self) -> Result<LiteNetwork, Box<dyn Error>> {
+        let (network, preprocessed) =
+            preprocess::preprocess_network(&self.shapefile, &self.network, &self.options()?)?;
+        match preprocessed {
+            Preprocessed::Read => {}
+            Preprocessed::Built => eprintln!("Built {:?}", self.network),
+            Preprocessed::Rebuilt(reason) => {
+                eprintln!("Rebuilt {:?}, it was stale ({})", self.network, reason)
+            }
+        }
+        Ok(network)
+    }
+
+    /// Where `preprocess --mapped` writes the memory-mapped network.
+    fn mapped_path(&self) -> PathBuf {
+        self.network.with_extension("map")
+    }
+
+    /// Opens the memory-mapped network, which must be written after the network. It is not
+    /// compared with the shapefile, the network is.
+    fn open_mapped(&self) -> Result<MappedNetwork, Box<dyn Error>> {
+        let path = self.mapped_path();
+        let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+        match (modified(&path), modified(&self.network)) {
+            (Err(_), _) => Err(format!(
+                "{:?} does not exist, write it with `preprocess --mapped`",
+                path
+            )
+            .into()),
+            (Ok(mapped), Ok(network)) if mapped < network => Err(format!(
+                "{:?} is older than {:?}, write it again with `preprocess --mapped`",
+                path, self.network
+            )
+            .into()),
+            _ => MappedNetwork::open(path),
+        }
+    }
+
+    /// Where the preprocessing of `algorithm` is stored, next to the network. Must be called
+    /// after the network is loaded or built.
+    fn algorithm_file(
+        &self,
+        algorithm: Algorithm,
+    ) -> Result<Option<AlgorithmFile>, Box<dyn Error>> {
+        let extension = match algorithm {
+            Algorithm::Dijkstra | Algorithm::Bidirectional | Algorithm::AStar => return Ok(None),
+            Algorithm::Ch => "ch.axe",
+            Algorithm::Hh => "hh.axe",
+            Algorithm::Hnr => "hnr.axe",
+        };
+        Ok(Some(AlgorithmFile {
+            path: self.network.with_extension(extension),
+            options: self.options()?,
+            source: file::read_source(&self.network)?,
+        }))
+    }
+}
+
+/// The preprocessing of an algorithm, stored with the header of the network it is built from.
+struct AlgorithmFile {
+    path: PathBuf,
+    options: BuildOptions,
+    source: Source,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Algorithm {
+    Dijkstra,
+    Bidirectional,
+    AStar,
+    /// Contraction hierarchies
+    Ch,
+    /// Highway hierarchies
+    Hh,
+    /// Highway node routing
+    Hnr,
+}
+
+impl Algorithm {
+    /// The preprocessed algorithms only find shortest distances.
+    fn supports(self, metric: Metric) -> bool {
+        metric == Metric::Distance
+            || matches!(
+                self,
+                Algorithm::Dijkstra | Algorithm::Bidirectional | Algorithm::AStar
+            )
+    }
+
+    fn check_supports(self, metric: Metric) -> Result<(), Box<dyn Error>> {
+        if self.supports(metric) {
+            Ok(())
+        } else {
+            Err(format!("{} can not route by {}", name(self), name(metric)).into())
+        }
+    }
+}
+
+/// What a path minimises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Metric {
+    Distance,
+    /// Travel time at the speed of every road
+    Time,
+}
+
+/// The name of a command line value.
+fn name<T: ValueEnum>(value: T) -> String {
+    value.to_possible_value().unwrap().get_name().to_string()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NodeSelector {
+    Junction(u32),
+    Coordinate(NodeCoord),
+}
+
+impl FromStr for NodeSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.split_once(',') {
+            Some((x, y)) => {
+                let x = x.trim().parse().map_err(|_| format!("invalid x: {}", s))?;
+                let y = y.trim().parse().map_err(|_| format!("invalid y: {}", s))?;
+                Ok(NodeSelector::Coordinate(NodeCoord { x, y }))
+            }
+            None => s
+                .parse()
+                .map(NodeSelector::Junction)
+                .map_err(|_| format!("not a junction id or coordinate: {}", s)),
+        }
+    }
+}
+
+impl NodeSelector {
+    fn resolve<S: Network>(&self, network: &S) -> Result<NodeId, Box<dyn Error>> {
+        match self {
+            NodeSelector::Junction(junction) => (0..network.nodes_len())
+                .map(NodeId::new)
+                .find(|node| network.junction_id(*node) == *junction)
+                .ok_or_else(|| format!("no junction with id {}", junction).into()),
+            NodeSelector::Coordinate(coord) => Ok(closest_node(network, *coord)),
+        }
+    }
+}
+
+/// One of the algorithms, the paths of all of them consist of edges of the original network.
+enum Router {
+    Dijkstra(DijkstraPathAlgorithm),
+    Bidirectional(BiDirDijkstraPathAlgorithm),
+    AStar(SimpleAStar<LiteNetwork>, StraightLineBound<TravelTime>),
+    Ch(ContractionHierarchyPathAlgorithm),
+    Hh(HighwayHierarchyPathAlgorithm),
+    Hnr(HighwayNodeRoutingPathAlgorithm),
+}
+
+/// A router that finds the paths that are shortest by `metric`.
+struct MetricRouter<'a> {
+    router: &'a Router,
+    metric: Metric,
+}
+
+impl PathAlgorithm for MetricRouter<'_> {
+    type Network = LiteNetwork;
+    type Output = u64;
+
+    fn path(&self, source: NodeId, target: NodeId) -> Result<(u64, Vec<EdgeId>), ()> {
+        self.router.path(self.metric, source, target)
+    }
+}
+
+impl Router {
+    /// Sets up the algorithm, the preprocessing is read from `stored` when it is current and
+    /// built otherwise.
+    fn new(
+        algorithm: Algorithm,
+        network: LiteNetwork,
+        stored: Option<&AlgorithmFile>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let router = match algorithm {
+            Algorithm::Dijkstra => Router::Dijkstra(DijkstraPathAlgorithm::new(network)),
+            Algorithm::Bidirectional => {
+                Router::Bidirectional(BiDirDijkstraPathAlgorithm::new(network))
+            }
+            Algorithm::AStar => {
+                let time = StraightLineBound::new(&network, TravelTime);
+                Router::AStar(SimpleAStar::new(network), time)
+            }
+            Algorithm::Ch => Router::Ch(ContractionHierarchyPathAlgorithm::with_hierarchy(
+                read_or_build(stored, || ContractionHierarchy::build(network))?,
+            )),
+            Algorithm::Hh => {
+                let hierarchy = read_or_build(stored, || {
+                    Ok::<_, Infallible>(HighwayHierarchy::build(
+                        &network,
+                        HighwayParameters::default(),
+                    ))
+                })?;
+                Router::Hh(HighwayHierarchyPathAlgorithm::with_hierarchy(
+                    network, hierarchy,
+                ))
+            }
+            Algorithm::Hnr => Router::Hnr(HighwayNodeRoutingPathAlgorithm::with_overlay(
+                read_or_build(stored, || build_overlay(network))?,
+            )),
+        };
+        Ok(router)
+    }
+
+    /// The cost and edges of the path that is shortest by `metric`, which the algorithm must
+    /// support.
+    fn path(
+        &self,
+        metric: Metric,
+        source: NodeId,
+        target: NodeId,
+    ) -> Result<(u64, Vec<EdgeId>), ()> {
+        match (self, metric) {
+            (Router::Dijkstra(a), Metric::Distance) => PathAlgorithm::path(a, source, target),
+            (Router::Dijkstra(a), Metric::Time) => a.path_with(TravelTime, source, target),
+            (Router::Bidirectional(a), Metric::Distance) => PathAlgorithm::path(a, source, target),
+            (Router::Bidirectional(a), Metric::Time) => a.path_with(TravelTime, source, target),
+            (Router::AStar(a, _), Metric::Distance) => PathAlgorithm::path(a, source, target),
+            (Router::AStar(a, time), Metric::Time) => {
+                a_star_path(a.network(), time, source, target)
+            }
+            (Router::Ch(a), Metric::Distance) => PathAlgorithm::path(a, source, target),
+            (Router::Hh(a), Metric::Distance) => PathAlgorithm::path(a, source, target),
+            (Router::Hnr(a), Metric::Distance) => PathAlgorithm::path(a, source, target),
+            (_, Metric::Time) => unreachable!("checked by Algorithm::check_supports"),
+        }
+    }
+
+    /// The paths between all pairs of nodes that are connected.
+    fn matrix(&self, metric: Metric, nodes: &[NodeId]) -> Vec<EdgePath> {
+        let result = match (self, metric) {
+            (Router::Dijkstra(a), Metric::Distance) => ManyToManyAlgorithm::path(a, nodes),
+            (Router::Ch(a), Metric::Distance) => ManyToManyAlgorithm::path(a, nodes),
+            (Router::Hh(a), Metric::Distance) => ManyToManyAlgorithm::path(a, nodes),
+            (Router::Hnr(a), Metric::Distance) => ManyToManyAlgorithm::path(a, nodes),
+            (Router::Bidirectional(a), Metric::Distance) => ManyToManyAlgorithm::path(a, nodes),
+            // Searches per pair, a* has no many-to-many variant and the others only find
+            // distances.
+            (Router::AStar(..), _) | (_, Metric::Time) => pairwise_paths(
+                &MetricRouter {
+                    router: self,
+                    metric,
+                },
+                nodes,
+            ),
+        };
+
+        match result {
+            Ok(paths) | Err(ManyManyErrors::NotAllPairsFound(paths)) => paths,
+            Err(ManyManyErrors::EmptyNodeList) => Vec::new(),
+        }
+    }
+
+    fn network(&self) -> &LiteNetwork {
+        match self {
+            Router::Dijkstra(a) => ManyToManyAlgorithm::network(a),
+            Router::Bidirectional(a) => ManyToManyAlgorithm::network(a),
+            Router::AStar(a, _) => a.network(),
+            Router::Ch(a) => a.hierarchy().network(),
+            Router::Hh(a) => ManyToManyAlgorithm::network(a),
+            Router::Hnr(a) => a.overlay().network(),
+        }
+    }
+}
+
+fn build_overlay(network: LiteNetwork) -> Result<MultiLevelOverlay, DistanceError> {
+    let hierarchy = HighwayHierarchy::build(&network, HighwayParameters::default());
+    let (ranking, level_sizes) =
+        HighwayNodeRoutingPathAlgorithm::hierarchy_ranking(&network, &hierarchy);
+    MultiLevelOverlay::build(network, &ranking, &level_sizes)
+}
+
+fn read_or_build<T, E, F>(stored: Option<&AlgorithmFile>, build: F) -> Result<T, Box<dyn Error>>
+where
+    T: Writeable,
+    E: Error + 'static,
+    F: FnOnce() -> Result<T, E>,
+{
+    if let Some(stored) = stored.filter(|stored| stored.path.exists()) {
+        let source = SourceCheck::Network(&stored.source);
+        match T::read(&stored.path, &stored.options, source) {
+            Ok(NetworkFile::Current(preprocessing)) => return Ok(preprocessing),
+            Ok(NetworkFile::Stale(reason)) => {
+                eprintln!("{:?} is stale ({}), building it again", stored.path, reason)
+            }
+            Err(error) => eprintln!(
+                "{:?} can not be read ({}), building it again",
+                stored.path, error
+            ),
+        }
+    }
+    Ok(build()?)
+}
+
+/// The edges of the shortest path on the memory-mapped network, for the algorithms without
+/// preprocessing.
+fn mapped_path(
+    network: &MappedNetwork,
+    algorithm: Algorithm,
+    metric: Metric,
+    source: NodeId,
+    target: NodeId,
+) -> Result<Vec<EdgeId>, Box<dyn Error>> {
+    let path = match (algorithm, metric) {
+        (Algorithm::Dijkstra, Metric::Distance) => {
+            dijkstra_path(network, Decimetres, source, target).map(|(_, edges)| edges)
+        }
+        (Algorithm::Dijkstra, Metric::Time) => {
+            dijkstra_path(network, TravelTime, source, target).map(|(_, edges)| edges)
+        }
+        (Algorithm::Bidirectional, Metric::Distance) => {
+            bidirectional_dijkstra(network, Decimetres, source, target).map(|(_, edges)| edges)
+        }
+        (Algorithm::Bidirectional, Metric::Time) => {
+            bidirectional_dijkstra(network, TravelTime, source, target).map(|(_, edges)| edges)
+        }
+        (Algorithm::AStar, Metric::Distance) => {
+            let bound = StraightLineBound::new(network, Decimetres);
+            a_star_path(network, &bound, source, target).map(|(_, edges)| edges)
+        }
+        (Algorithm::AStar, Metric::Time) => {
+            let bound = StraightLineBound::new(network, TravelTime);
+            a_star_path(network, &bound, source, target).map(|(_, edges)| edges)
+        }
+        _ => return Err(format!("{} does not run on the mapped network", name(algorithm)).into()),
+    };
+    path.map_err(|_| "no path between the nodes".into())
+}
+
+fn path_distance<S: Network>(network: &S, edges: &[EdgeId]) -> f32 {
+    edges.iter().map(|edge| network.edge_distance(*edge)).sum()
+}
+
+/// Travel time in seconds.
+fn path_time<S: Network>(network: &S, edges: &[EdgeId]) -> f64 {
+    weight::path_weight(network, TravelTime, edges) as f64 / 1000.0
+}
+
+impl Cli {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        match self.command {
+            Command::Preprocess {
+                files,
+                algorithm,
+                mapped,
+            } => preprocess(&files, algorithm, mapped),
+            Command::Query {
+                files,
+                algorithm,
+                metric,
+                from,
+                to,
+                roads,
+                mapped,
+            } => query(&files, algorithm, metric, from, to, roads, mapped),
+            Command::Matrix {
+                files,
+                algorithm,
+                metric,
+                nodes,
+                random,
+                seed,
+                output,
+            } => matrix(&files, algorithm, metric, nodes, random, seed, output),
+            Command::Stats {
+                files,
+                algorithm,
+                core,
+            } => stats(&files, algorithm, core),
+            Command::Verify {
+                files,
+                algorithms,
+                metric,
+                pairs,
+                seed,
+            } => verify(&files, algorithms, metric, pairs, seed),
+        }
+    }
+}
+
+fn preprocess(
+    files: &NetworkFiles,
+    algorithm: Algorithm,
+    mapped: bool,
+) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let network: LiteNetwork =
+        preprocess::build_network(&files.shapefile, &files.network, &files.options()?)?;
+    println!(
+        "Network: {} nodes, {} edges in {:?}",
+        network.nodes_len(),
+        network.edge_len(),
+        start.elapsed()
+    );
+
+    if mapped {
+        MappedNetwork::write(&network, files.mapped_path())?;
+        println!("Mapped network written to {:?}", files.mapped_path());
+    }
+
+    let stored = match files.algorithm_file(algorithm)? {
+        Some(stored) => stored,
+        None => return Ok(()),
+    };
+
+    let start = Instant::now();
+    let router = Router::new(algorithm, network, None)?;
+    let (path, options, source) = (&stored.path, &stored.options, &stored.source);
+    match &router {
+        Router::Ch(a) => a.hierarchy().write(path, options, source)?,
+        Router::Hh(a) => a.hierarchy().write(path, options, source)?,
+        Router::Hnr(a) => a.overlay().write(path, options, source)?,
+        Router::Dijkstra(_) | Router::Bidirectional(_) | Router::AStar(..) => unreachable!(),
+    }
+    println!(
+        "Preprocessing written to {:?} in {:?}",
+        path,
+        start.elapsed()
+    );
+    print_preprocessing(&router);
+
+    Ok(())
+}
+
+fn query(
+    files: &NetworkFiles,
+    algorithm: Algorithm,
+    metric: Metric,
+    from: NodeSelector,
+    to: NodeSelector,
+    roads: bool,
+    mapped: bool,
+) -> Result<(), Box<dyn Error>> {
+    algorithm.check_supports(metric)?;
+    if mapped {
+        let network = files.open_mapped()?;
+        let source = from.resolve(&network)?;
+        let target = to.resolve(&network)?;
+
+        let start = Instant::now();
+        let edges = mapped_path(&network, algorithm, metric, source, target)?;
+        print_path(&network, source, target, &edges, start.elapsed(), roads);
+        return Ok(());
+    }
+
+    let network = files.load()?;
+    let source = from.resolve(&network)?;
+    let target = to.resolve(&network)?;
+
+    let stored = files.algorithm_file(algorithm)?;
+    let router = Router::new(algorithm, network, stored.as_ref())?;
+    let network = router.network();
+
+    let start = Instant::now();
+    let (_, edges) = router
+        .path(metric, source, target)
+        .map_err(|_| "no path between the nodes")?;
+    print_path(network, source, target, &edges, start.elapsed(), roads);
+
+    Ok(())
+}
+
+/// Prints the length and travel time of the path, and its roads when `roads` is set.
+fn print_path<S: Network>(
+    network: &S,
+    source: NodeId,
+    target: NodeId,
+    edges: &[EdgeId],
+    duration: Duration,
+    roads: bool,
+) {
+    println!(
+        "From junction {} to junction {}: {} m, {:.1} s over {} roads in {:?}",
+        network.junction_id(source),
+        network.junction_id(target),
+        path_distance(network, edges),
+        path_time(network, edges),
+        edges.len(),
+        duration
+    );
+
+    if roads {
+        for edge in edges.iter().copied() {
+            match network.edge_metadata(edge) {
+                Some(metadata) => println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    network.edge_object_id(edge),
+                    metadata.street_name,
+                    metadata.road_number,
+                    metadata.municipality,
+                    metadata.road_type
+                ),
+                None => println!("{}", network.edge_object_id(edge)),
+            }
+        }
+    }
+}
+
+fn matrix(
+    files: &NetworkFiles,
+    algorithm: Algorithm,
+    metric: Metric,
+    nodes: Option<PathBuf>,
+    random: Option<usize>,
+    seed: u64,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    algorithm.check_supports(metric)?;
+    let network = files.load()?;
+
+    let nodes = match (nodes, random) {
+        (Some(file), _) => fs::read_to_string(file)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.parse::<NodeSelector>()?.resolve(&network))
+            .collect::<Result<Vec<_>, _>>()?,
+        (None, Some(amount)) => StdRng::seed_from_u64(seed)
+            .sample_iter(Uniform::new(0, network.nodes_len()))
+            .map(NodeId::new)
+            .take(amount)
+            .collect(),
+        (None, None) => return Err("either a node file or a random amount is needed".into()),
+    };
+
+    let stored = files.algorithm_file(algorithm)?;
+    let router = Router::new(algorithm, network, stored.as_ref())?;
+    let network = router.network();
+
+    let start = Instant::now();
+    let paths = router.matrix(metric, &nodes);
+    eprintln!(
+        "{} of {} paths found in {:?}",
+        paths.len(),
+        nodes.len() * nodes.len().saturating_sub(1),
+        start.elapsed()
+    );
+
+    let mut distances = vec![vec![None; nodes.len()]; nodes.len()];
+    for path in &paths {
+        let source = nodes.iter().position(|node| *node == path.source).unwrap();
+        let target = nodes.iter().position(|node| *node == path.target).unwrap();
+        distances[source][target] = Some(match metric {
+            Metric::Distance => path_distance(network, &path.edges) as f64,
+            Metric::Time => path_time(network, &path.edges),
+        });
+    }
+
+    let junctions = nodes
+        .iter()
+        .map(|node| network.junction_id(*node).to_string())
+        .collect::<Vec<_>>();
+    let mut table = format!(",{}\n", junctions.join(","));
+    for (junction, row) in junctions.iter().zip(distances) {
+        let row = row
+            .iter()
+            .map(|distance| distance.map_or(String::new(), |d| d.to_string()))
+            .collect::<Vec<_>>();
+        table.push_str(&format!("{},{}\n", junction, row.join(",")));
+    }
+
+    match output {
+        Some(output) => fs::write(output, table)?,
+        None => print!("{}", table),
+    }
+
+    Ok(())
+}
+
+fn stats(files: &NetworkFiles, algorithm: Algorithm, core: bool) -> Result<(), Box<dyn Error>> {
+    let network = files.load()?;
+    let nodes = (0..network.nodes_len()).map(NodeId::new);
+
+    let length: f64 = (0..network.edge_len())
+        .map(|edge| network.edge_distance(EdgeId::new(edge)) as f64)
+        .sum();
+    let max_degree = nodes
+        .clone()
+        .map(|node| network.outgoing_edges(node).len())
+        .max()
+        .unwrap_or(0);
+    let mut degrees = vec![0; max_degree + 1];
+    for node in nodes.clone() {
+        degrees[network.outgoing_edges(node).len()] += 1;
+    }
+    let no_incoming = nodes
+        .filter(|node| network.incoming_edges(*node).is_empty())
+        .count();
+
+    println!("Nodes: {}", network.nodes_len());
+    println!("Edges: {}", network.edge_len());
+    println!("Total length: {:.1} km", length / 1000.0);
+    println!(
+        "Average out degree: {:.2}",
+        network.edge_len() as f64 / network.nodes_len() as f64
+    );
+    for (degree, count) in degrees.iter().enumerate() {
+        println!("Out degree {}: {} nodes", degree, count);
+    }
+    println!("Without incoming edges: {} nodes", no_incoming);
+
+    if core {
+        let start = Instant::now();
+        let core = CoreNetwork::contract(network.clone(), &[]);
+        println!(
+            "Core network: {} nodes, {} shortcuts, contracted in {:?}",
+            core.core_nodes().count(),
+            core.shortcuts().len(),
+            start.elapsed()
+        );
+    }
+
+    let stored = match files.algorithm_file(algorithm)? {
+        Some(stored) => stored,
+        None => return Ok(()),
+    };
+
+    let start = Instant::now();
+    let router = Router::new(algorithm, network, Some(&stored))?;
+    println!("Preprocessing loaded or built in {:?}", start.elapsed());
+
+    print_preprocessing(&router);
+
+    Ok(())
+}
+
+/// Prints the size of the preprocessing of the algorithm.
+fn print_preprocessing(router: &Router) {
+    match router {
+        Router::Ch(a) => println!("Shortcuts: {}", a.hierarchy().shortcuts().len()),
+        Router::Hh(a) => println!("Levels: {}", a.hierarchy().top_level()),
+        Router::Hnr(a) => {
+            let overlay = a.overlay();
+            for level in 1..=overlay.top_level() {
+                println!(
+                    "Level {}: {} highway nodes",
+                    level,
+                    overlay.level_nodes(level).len()
+                );
+            }
+            println!("Overlay edges: {}", overlay.shortcuts().len());
+        }
+        Router::Dijkstra(_) | Router::Bidirectional(_) | Router::AStar(..) => {}
+    }
+}
+
+fn verify(
+    files: &NetworkFiles,
+    mut algorithms: Vec<Algorithm>,
+    metric: Metric,
+    pairs: usize,
+    seed: u64,
+) -> Result<(), Box<dyn Error>> {
+    if algorithms.is_empty() {
+        algorithms = Algorithm::value_variants()
+            .iter()
+            .copied()
+            .filter(|algorithm| algorithm.supports(metric))
+            .collect();
+    }
+    for algorithm in &algorithms {
+        algorithm.check_supports(metric)?;
+    }
+    let network = files.load()?;
+
+    let mut routers = Vec::new();
+    for algorithm in &algorithms {
+        let start = Instant::now();
+        let stored = files.algorithm_file(*algorithm)?;
+        routers.push(Router::new(*algorithm, network.clone(), stored.as_ref())?);
+        eprintln!("{:?} ready in {:?}", algorithm, start.elapsed());
+    }
+
+    let names = algorithms.iter().copied().map(name).collect::<Vec<_>>();
+    let paths = routers
+        .iter()
+        .map(|router| move |source, target| router.path(metric, source, target))
+        .collect::<Vec<_>>();
+    let compared = names
+        .iter()
+        .zip(&paths)
+        .map(|(name, path)| (name.as_str(), path as &PathFn))
+        .collect::<Vec<_>>();
+
+    let pairs = verify::random_pairs(&network, pairs, seed);
+    let start = Instant::now();
+    let mismatches = match metric {
+        Metric::Distance => verify::verify(&network, Decimetres, &compared, &pairs),
+        Metric::Time => verify::verify(&network, TravelTime, &compared, &pairs),
+    };
+    eprintln!(
+        "{} pairs verified with {} algorithms in {:?}",
+        pairs.len(),
+        compared.len(),
+        start.elapsed()
+    );
+
+    for mismatch in &mismatches {
+        println!(
+            "{} from junction {} to junction {}: {}",
+            mismatch.algorithm,
+            network.junction_id(mismatch.source),
+            network.junction_id(mismatch.target),
+            mismatch.problem
+        );
+    }
+
+    match mismatches.len() {
+        0 => Ok(()),
+        count => Err(format!("{} mismatches, the reference is {}", count, names[0]).into()),
+    }
+}
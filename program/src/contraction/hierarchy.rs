@@ -0,0 +1,169 @@
+// Contraction hierarchies, following "Contraction Hierarchies: Faster and Simpler Hierarchical
+// Routing in Road Networks" (Geisberger, Sanders, Schultes, Delling).
+//
+// Nodes are contracted one at a time, the next node is the one with the smallest edge difference
+// (shortcuts added - edges removed) plus the amount of contracted neighbours. The order of
+// contraction is the rank of a node. A query only relaxes edges towards higher ranked nodes, in
+// the forward search on outgoing edges and in the backward search on incoming edges.
+use super::needed_shortcuts;
+use crate::{
+    algorithm::{dijkstra::DijkstraDirection, EdgePath},
+    network::{
+        builder::DistanceError, file::Layout, EdgeId, LiteNetwork, Network, NodeId, Shortcuts,
+    },
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContractionHierarchy {
+    // The original network with all shortcuts appended.
+    network: LiteNetwork,
+    shortcuts: Shortcuts,
+    // Position of every node in the contraction order.
+    rank: Vec<usize>,
+    // For every node the outgoing edges to higher ranked nodes.
+    upward: Vec<Vec<EdgeId>>,
+    // For every node the incoming edges from higher ranked nodes.
+    downward: Vec<Vec<EdgeId>>,
+}
+
+impl Layout for ContractionHierarchy {
+    const LAYOUT: &'static str = "contraction hierarchy";
+}
+
+impl ContractionHierarchy {
+    /// Contracts every node of `network`. Fails when a shortcut is too long to store.
+    pub fn build(mut network: LiteNetwork) -> Result<Self, DistanceError> {
+        // The network the nodes are removed from, the shortcuts are added to both networks so
+        // they get the same ids.
+        let mut remaining = network.clone();
+        let mut shortcuts = Shortcuts::new(network.edge_len());
+        let mut contracted = vec![false; network.nodes_len()];
+        let mut contracted_neighbours = vec![0i64; network.nodes_len()];
+        let mut rank = vec![0; network.nodes_len()];
+        let mut next_rank = 0;
+
+        let mut heap = (0..network.nodes_len())
+            .into_par_iter()
+            .map(|node| {
+                Reverse((
+                    priority(&remaining, NodeId::new(node), 0),
+                    NodeId::new(node),
+                ))
+            })
+            .collect::<BinaryHeap<_>>();
+
+        while let Some(Reverse((_, node))) = heap.pop() {
+            if contracted[node] {
+                continue;
+            }
+
+            // Priorities of nodes that are not neighbours get outdated as well, they are only
+            // updated when they come up.
+            let current = priority(&remaining, node, contracted_neighbours[node]);
+            if let Some(Reverse((next, _))) = heap.peek() {
+                if current > *next {
+                    heap.push(Reverse((current, node)));
+                    continue;
+                }
+            }
+
+            let needed = needed_shortcuts(&remaining, node);
+            let (incoming, outgoing) = remaining.disconnect_node(node);
+            contracted[node] = true;
+            rank[node] = next_rank;
+            next_rank += 1;
+
+            for (first, second, distance) in needed {
+                let source = remaining.edge_source(first);
+                let target = remaining.edge_target(second);
+                let shortcut = remaining.add_shortcut(source, target, distance)?;
+                network.add_shortcut(source, target, distance)?;
+                shortcuts.insert(shortcut, &[first, second]);
+            }
+
+            let mut neighbours = incoming
+                .iter()
+                .map(|edge| remaining.edge_source(*edge))
+                .chain(outgoing.iter().map(|edge| remaining.edge_target(*edge)))
+                .filter(|neighbour| !contracted[*neighbour])
+                .collect::<Vec<_>>();
+            neighbours.sort();
+            neighbours.dedup();
+
+            for neighbour in neighbours {
+                contracted_neighbours[neighbour] += 1;
+                let priority = priority(&remaining, neighbour, contracted_neighbours[neighbour]);
+                heap.push(Reverse((priority, neighbour)));
+            }
+        }
+
+        let mut upward = vec![Vec::new(); network.nodes_len()];
+        let mut downward = vec![Vec::new(); network.nodes_len()];
+        for edge in (0..network.edge_len()).map(EdgeId::new) {
+            let source = network.edge_source(edge);
+            let target = network.edge_target(edge);
+            if rank[source] < rank[target] {
+                upward[source].push(edge);
+            } else if rank[source] > rank[target] {
+                downward[target].push(edge);
+            }
+        }
+
+        network.compact();
+
+        Ok(Self {
+            network,
+            shortcuts,
+            rank,
+            upward,
+            downward,
+        })
+    }
+
+    /// The original network with all shortcuts, paths on it can be unpacked with `unpack_path`.
+    pub fn network(&self) -> &LiteNetwork {
+        &self.network
+    }
+
+    pub fn shortcuts(&self) -> &Shortcuts {
+        &self.shortcuts
+    }
+
+    /// The edges towards higher ranked nodes that leave (forward) or enter (backward) `node`,
+    /// together with the node on the other end.
+    pub fn upward_neighbours<'a>(
+        &'a self,
+        node: NodeId,
+        direction: &DijkstraDirection,
+    ) -> impl Iterator<Item = (NodeId, EdgeId)> + 'a {
+        let (edges, forward) = match direction {
+            DijkstraDirection::Forward => (&self.upward[node], true),
+            DijkstraDirection::Backward => (&self.downward[node], false),
+        };
+
+        edges.iter().map(move |edge| {
+            if forward {
+                (self.network.edge_target(*edge), *edge)
+            } else {
+                (self.network.edge_source(*edge), *edge)
+            }
+        })
+    }
+
+    /// Replaces the shortcuts in a path by the original edges.
+    pub fn unpack_path(&self, path: &EdgePath) -> EdgePath {
+        self.shortcuts.unpack_path(path)
+    }
+}
+
+/// Edge difference of contracting `node` plus its contracted neighbours, lower is contracted
+/// first.
+fn priority(network: &LiteNetwork, node: NodeId, contracted_neighbours: i64) -> i64 {
+    let degree = network.incoming_edges(node).len() + network.outgoing_edges(node).len();
+    let shortcuts = needed_shortcuts(network, node).len();
+
+    shortcuts as i64 - degree as i64 + contracted_neighbours
+}
@@ -0,0 +1,187 @@
+// Contraction of bypassable nodes.
+//
+// A node is bypassed by removing it from the network and connecting every predecessor to every
+// successor with a shortcut edge, unless a witness path shows the shortcut is not needed.
+// Only nodes that need at most as many shortcuts as they have edges are bypassed, these are
+// mostly the in2/out1/out2 nodes in the middle of a road. What is left is the core network.
+//
+// Contracting every node one by one, in order of importance, gives a contraction hierarchy.
+pub mod hierarchy;
+pub mod witness;
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    algorithm::EdgePath,
+    network::{EdgeId, LiteNetwork, Network, NodeId, Shortcuts},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    convert::TryFrom,
+};
+
+/// Nodes with more edges than this are never bypassed.
+pub const MAX_BYPASS_DEGREE: usize = 6;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CoreNetwork {
+    // The contracted nodes have no edges left, node ids are the same as in the original network.
+    network: LiteNetwork,
+    contracted: Vec<bool>,
+    shortcuts: Shortcuts,
+}
+
+impl CoreNetwork {
+    /// Bypasses all bypassable nodes of the network, except for the nodes in `keep`.
+    pub fn contract(mut network: LiteNetwork, keep: &[NodeId]) -> Self {
+        let keep = keep.iter().copied().collect::<HashSet<_>>();
+        let mut contracted = vec![false; network.nodes_len()];
+        let mut queued = vec![true; network.nodes_len()];
+        let mut queue = (0..network.nodes_len())
+            .map(NodeId::new)
+            .collect::<VecDeque<_>>();
+        let mut shortcuts = Shortcuts::new(network.edge_len());
+
+        while let Some(node) = queue.pop_front() {
+            queued[node] = false;
+            if keep.contains(&node) {
+                continue;
+            }
+
+            let needed = match bypass_shortcuts(&network, node) {
+                Some(needed) => needed,
+                None => continue,
+            };
+
+            let (incoming, outgoing) = network.disconnect_node(node);
+            contracted[node] = true;
+
+            for (first, second, distance) in needed {
+                let source = network.edge_source(first);
+                let target = network.edge_target(second);
+                let shortcut = network
+                    .add_shortcut(source, target, distance)
+                    .expect("checked by bypass_shortcuts");
+                shortcuts.insert(shortcut, &[first, second]);
+            }
+
+            // Neighbours lost an edge, so they might have become bypassable.
+            let neighbours = incoming
+                .iter()
+                .map(|edge| network.edge_source(*edge))
+                .chain(outgoing.iter().map(|edge| network.edge_target(*edge)));
+
+            for neighbour in neighbours {
+                if !queued[neighbour] && !contracted[neighbour] {
+                    queued[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        network.compact();
+
+        Self {
+            network,
+            contracted,
+            shortcuts,
+        }
+    }
+
+    /// The core network, routing between core nodes gives the same distances as in the original.
+    pub fn network(&self) -> &LiteNetwork {
+        &self.network
+    }
+
+    pub fn core_nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.contracted.len())
+            .map(NodeId::new)
+            .filter(move |node| !self.contracted[*node])
+    }
+
+    pub fn shortcuts(&self) -> &Shortcuts {
+        &self.shortcuts
+    }
+
+    /// Replaces the shortcuts in a path on the core network by the original edges.
+    pub fn unpack_path(&self, path: &EdgePath) -> EdgePath {
+        self.shortcuts.unpack_path(path)
+    }
+}
+
+/// The shortcuts (incoming edge, outgoing edge, decimetres) that are needed to bypass `node`.
+/// Returns `None` when the node is not bypassable, which it also is not when a shortcut is too
+/// long to store.
+fn bypass_shortcuts<S: Network>(network: &S, node: NodeId) -> Option<Vec<(EdgeId, EdgeId, u64)>> {
+    let degree = network.incoming_edges(node).len() + network.outgoing_edges(node).len();
+
+    if degree == 0 || degree > MAX_BYPASS_DEGREE {
+        return None;
+    }
+
+    let needed = needed_shortcuts(network, node);
+
+    if needed.len() > degree
+        || needed
+            .iter()
+            .any(|(_, _, distance)| u32::try_from(*distance).is_err())
+    {
+        return None;
+    }
+
+    Some(needed)
+}
+
+/// The shortcuts (incoming edge, outgoing edge, decimetres) that keep all distances the same when
+/// `node` is removed from the network, at most one for every pair of neighbours.
+pub fn needed_shortcuts<S: Network>(network: &S, node: NodeId) -> Vec<(EdgeId, EdgeId, u64)> {
+    let outgoing = network.outgoing_edges(node);
+    let mut needed: HashMap<(NodeId, NodeId), (EdgeId, EdgeId, u64)> = HashMap::new();
+
+    for in_edge in network.incoming_edges(node) {
+        let source = network.edge_source(*in_edge);
+        if source == node {
+            continue;
+        }
+
+        let candidates = outgoing
+            .iter()
+            .map(|out_edge| {
+                (
+                    network.edge_target(*out_edge),
+                    *out_edge,
+                    u64::from(network.edge_decimetres(*in_edge))
+                        + u64::from(network.edge_decimetres(*out_edge)),
+                )
+            })
+            .filter(|(target, _, _)| *target != source && *target != node)
+            .collect::<Vec<_>>();
+
+        let max_distance = candidates
+            .iter()
+            .map(|(_, _, distance)| *distance)
+            .max()
+            .unwrap_or(0);
+        let witnesses = witness::witness_distances(network, source, node, max_distance);
+
+        for (target, out_edge, distance) in candidates {
+            if witnesses.get(&target).is_some_and(|w| *w <= distance) {
+                continue;
+            }
+
+            let shortcut = needed
+                .entry((source, target))
+                .or_insert((*in_edge, out_edge, distance));
+            if distance < shortcut.2 {
+                *shortcut = (*in_edge, out_edge, distance);
+            }
+        }
+    }
+
+    let mut needed = needed.into_values().collect::<Vec<_>>();
+    needed.sort_by_key(|(first, second, _)| (*first, *second));
+
+    needed
+}
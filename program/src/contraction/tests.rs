@@ -0,0 +1,156 @@
+// The core network of generated networks, compared with Dijkstra on the original network. Paths
+// on the core network are unpacked to original edges, which must be the same roads.
+use super::{hierarchy::ContractionHierarchy, CoreNetwork};
+use crate::{
+    algorithm::{
+        dijkstra::dijkstra_path,
+        weight::{self, Decimetres},
+        EdgePath,
+    },
+    network::{
+        generate, network_lite::SHORTCUT_OBJECT_ID, EdgeId, LiteNetwork, LiteNetworkBuilder,
+        Network, NodeId,
+    },
+};
+use std::collections::HashMap;
+
+/// A grid, whose nodes on the border are bypassable, and a random geometric network with many
+/// nodes of a low degree.
+fn networks() -> Vec<LiteNetwork> {
+    vec![
+        generate::grid(LiteNetworkBuilder::new(), 8, 6, 100.0),
+        generate::random_geometric(LiteNetworkBuilder::new(), 150, 1000.0, 120.0, 3),
+    ]
+}
+
+/// The network contracted with every fifth node kept, and the nodes that are left.
+fn contract(network: &LiteNetwork) -> (CoreNetwork, Vec<NodeId>) {
+    let keep = (0..network.nodes_len())
+        .step_by(5)
+        .map(NodeId::new)
+        .collect::<Vec<_>>();
+    let core = CoreNetwork::contract(network.clone(), &keep);
+    let core_nodes = core.core_nodes().collect::<Vec<_>>();
+
+    assert!(keep.iter().all(|node| core_nodes.contains(node)));
+    assert!(core_nodes.len() < network.nodes_len());
+    assert_ne!(core.shortcuts().len(), 0);
+    (core, core_nodes)
+}
+
+#[test]
+fn core_distances_match_dijkstra() {
+    for network in networks() {
+        let (core, core_nodes) = contract(&network);
+
+        for source in &core_nodes {
+            let expected = network
+                .forward_dijkstra(*source)
+                .filter(|(_, node)| core_nodes.contains(node))
+                .map(|(cost, node)| (node, cost))
+                .collect::<HashMap<_, _>>();
+            let found = core
+                .network()
+                .forward_dijkstra(*source)
+                .map(|(cost, node)| (node, cost))
+                .collect::<HashMap<_, _>>();
+            // Contracted nodes have no edges left, so only core nodes are found.
+            assert_eq!(found, expected, "from {:?}", source);
+        }
+    }
+}
+
+#[test]
+fn unpacked_paths_are_original_roads() {
+    for network in networks() {
+        let (core, core_nodes) = contract(&network);
+        let mut shortcuts_used = 0;
+
+        for source in core_nodes.iter().step_by(3) {
+            for target in core_nodes.iter().step_by(7) {
+                let (cost, edges) =
+                    match dijkstra_path(core.network(), Decimetres, *source, *target) {
+                        Ok(path) => path,
+                        Err(()) => continue,
+                    };
+                shortcuts_used += edges
+                    .iter()
+                    .filter(|edge| core.shortcuts().is_shortcut(**edge))
+                    .count();
+
+                let path = core.unpack_path(&EdgePath {
+                    source: *source,
+                    target: *target,
+                    edges,
+                });
+                assert_eq!((path.source, path.target), (*source, *target));
+                check_original_path(&network, &core, &path);
+                assert_eq!(weight::path_weight(&network, Decimetres, &path.edges), cost);
+            }
+        }
+        assert_ne!(shortcuts_used, 0);
+    }
+}
+
+/// Checks that `path` is a connected chain of original edges from its source to its target, with
+/// the object ids of the roads they are part of.
+fn check_original_path(network: &LiteNetwork, core: &CoreNetwork, path: &EdgePath) {
+    let mut node = path.source;
+    for edge in &path.edges {
+        assert!(
+            edge.index() < network.edge_len(),
+            "{:?} is a shortcut",
+            edge
+        );
+        assert_eq!(network.edge_source(*edge), node);
+        node = network.edge_target(*edge);
+
+        let object_id = core.network().edge_object_id(*edge);
+        assert_ne!(object_id, SHORTCUT_OBJECT_ID);
+        assert_eq!(object_id, network.edge_object_id(*edge));
+    }
+    assert_eq!(node, path.target);
+}
+
+#[test]
+fn shortcuts_have_no_object_id() {
+    for network in networks() {
+        let (core, _) = contract(&network);
+        let core_network = core.network();
+
+        for edge in (network.edge_len()..core_network.edge_len()).map(EdgeId::new) {
+            assert!(core.shortcuts().is_shortcut(edge));
+            assert_eq!(core_network.edge_object_id(edge), SHORTCUT_OBJECT_ID);
+
+            // A shortcut is as long as the original edges behind it.
+            let mut unpacked = Vec::new();
+            core.shortcuts().unpack_edge(edge, &mut unpacked);
+            let path = EdgePath {
+                source: core_network.edge_source(edge),
+                target: core_network.edge_target(edge),
+                edges: unpacked,
+            };
+            check_original_path(&network, &core, &path);
+            assert_eq!(
+                weight::path_weight(&network, Decimetres, &path.edges),
+                u64::from(core_network.edge_decimetres(edge))
+            );
+        }
+    }
+}
+
+/// Node 0 is between two roads that are together longer than a distance can be stored, so it
+/// can not be bypassed.
+#[test]
+fn shortcuts_too_long_to_store_are_not_added() {
+    let locations = [(1.0, 0.0), (0.0, 0.0), (2.0, 0.0)];
+    let edges = [(1, 0, 400_000_000.0), (0, 2, 400_000_000.0)];
+    let network = generate::edge_list(LiteNetworkBuilder::new(), &locations, &edges);
+
+    let core = CoreNetwork::contract(network.clone(), &[]);
+    assert_eq!(core.core_nodes().collect::<Vec<_>>(), vec![NodeId(0)]);
+    assert!(core.shortcuts().is_empty());
+
+    // A hierarchy contracts every node.
+    assert!(ContractionHierarchy::build(network).is_err());
+}
@@ -0,0 +1,44 @@
+use crate::network::{Network, NodeId};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// Maximum amount of nodes a witness search settles before giving up.
+pub const WITNESS_SETTLE_LIMIT: usize = 100;
+
+/// Distances in decimetres from `source` to the nodes settled by a local search that never passes `avoid`.
+///
+/// The search stops at `max_distance` or after `WITNESS_SETTLE_LIMIT` settled nodes, so a node
+/// missing from the result might still have a witness. That only costs an unneeded shortcut.
+pub fn witness_distances<S: Network>(
+    network: &S,
+    source: NodeId,
+    avoid: NodeId,
+    max_distance: u64,
+) -> HashMap<NodeId, u64> {
+    let mut settled = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    heap.push(Reverse((0, source)));
+
+    while let Some(Reverse((distance, node))) = heap.pop() {
+        if distance > max_distance || settled.len() >= WITNESS_SETTLE_LIMIT {
+            break;
+        }
+        if settled.contains_key(&node) {
+            continue;
+        }
+        settled.insert(node, distance);
+
+        for edge in network.outgoing_edges(node) {
+            let target = network.edge_target(*edge);
+            if target != avoid && !settled.contains_key(&target) {
+                let distance = distance + u64::from(network.edge_decimetres(*edge));
+                heap.push(Reverse((distance, target)));
+            }
+        }
+    }
+
+    settled
+}
@@ -1,10 +1,24 @@
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct F32Wrapper(pub f32);
 
+impl PartialOrd for F32Wrapper {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Ord for F32Wrapper {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.0.partial_cmp(&other.0).unwrap()
     }
 }
 
-impl Eq for F32Wrapper {}
\ No newline at end of file
+impl Eq for F32Wrapper {}
+
+impl std::ops::Add for F32Wrapper {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        F32Wrapper(self.0 + other.0)
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{
+    algorithm::weight::{Decimetres, Metric},
+    network::{EdgeId, Network, NodeId},
+};
+use rayon::prelude::*;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// Selects the highway edges of `level`, these make up the next level.
+///
+/// For every node a partial shortest path tree is grown in which a node turns passive when it is
+/// settled while the forward neighbourhood of s1, the first node after the root, and its own
+/// backward neighbourhood no longer overlap. Children of passive nodes are passive as well.
+/// The tree is complete when no active nodes are left in the queue.
+pub fn select_highway_edges<S: Network + Sync>(
+    network: &S,
+    edge_level: &[u8],
+    level: u8,
+    nodes: &[NodeId],
+    forward_radius: &[u64],
+    backward_radius: &[u64],
+) -> Vec<EdgeId> {
+    let mut highway_edges = nodes
+        .par_iter()
+        .map(|root| {
+            let tree = PartialTree::grow(
+                network,
+                edge_level,
+                level,
+                *root,
+                forward_radius,
+                backward_radius,
+            );
+            tree.highway_edges(network, forward_radius, backward_radius)
+        })
+        .flat_map_iter(|x| x.into_iter())
+        .collect::<Vec<_>>();
+
+    highway_edges.sort();
+    highway_edges.dedup();
+    highway_edges
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TreeLabel {
+    cost: u64,
+    parent: Option<EdgeId>,
+    // The first node after the root on the path towards this node.
+    s1: NodeId,
+    active: bool,
+    settled: bool,
+}
+
+struct PartialTree {
+    root: NodeId,
+    labels: HashMap<NodeId, TreeLabel>,
+    settled: Vec<NodeId>,
+}
+
+impl PartialTree {
+    fn grow<S: Network>(
+        network: &S,
+        edge_level: &[u8],
+        level: u8,
+        root: NodeId,
+        forward_radius: &[u64],
+        backward_radius: &[u64],
+    ) -> Self {
+        let mut labels = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        let mut settled = Vec::new();
+        let mut active_unsettled = 1;
+
+        labels.insert(
+            root,
+            TreeLabel {
+                cost: 0,
+                parent: None,
+                s1: root,
+                active: true,
+                settled: false,
+            },
+        );
+        heap.push(Reverse((0u64, root)));
+
+        while active_unsettled > 0 {
+            let (cost, node) = match heap.pop() {
+                Some(Reverse(entry)) => entry,
+                None => break,
+            };
+
+            let mut label = labels[&node];
+            if label.settled || label.cost != cost {
+                continue;
+            }
+
+            if label.active {
+                active_unsettled -= 1;
+
+                // The node turns passive when the neighbourhoods of s1 and the node do not overlap.
+                if node != root {
+                    let s1_cost = labels[&label.s1].cost;
+                    label.active = cost - s1_cost
+                        <= forward_radius[label.s1.index()]
+                            .saturating_add(backward_radius[node.index()]);
+                }
+            }
+
+            label.settled = true;
+            labels.insert(node, label);
+            settled.push(node);
+
+            for edge in network.outgoing_edges(node) {
+                if edge_level[edge.index()] < level {
+                    continue;
+                }
+
+                let target = network.edge_target(*edge);
+                let cost = label.cost + Decimetres.weight(network, *edge);
+                let s1 = if node == root { target } else { label.s1 };
+
+                let new_label = TreeLabel {
+                    cost,
+                    parent: Some(*edge),
+                    s1,
+                    active: label.active,
+                    settled: false,
+                };
+
+                match labels.get_mut(&target) {
+                    Some(existing) if existing.settled || existing.cost <= cost => continue,
+                    Some(existing) => {
+                        if existing.active {
+                            active_unsettled -= 1;
+                        }
+                        *existing = new_label;
+                    }
+                    None => {
+                        labels.insert(target, new_label);
+                    }
+                }
+
+                if new_label.active {
+                    active_unsettled += 1;
+                }
+                heap.push(Reverse((cost, target)));
+            }
+        }
+
+        Self {
+            root,
+            labels,
+            settled,
+        }
+    }
+
+    /// Edges (u, v) on a tree path (root, .., u, v, .., t) where v is outside the forward
+    /// neighbourhood of the root and u is outside the backward neighbourhood of t.
+    fn highway_edges<S: Network>(
+        &self,
+        network: &S,
+        forward_radius: &[u64],
+        backward_radius: &[u64],
+    ) -> Vec<EdgeId> {
+        let root_radius = forward_radius[self.root.index()];
+
+        // Slack of a node: the smallest remaining backward radius of any node in its subtree.
+        let mut slack: HashMap<NodeId, i64> = self
+            .settled
+            .iter()
+            .map(|node| (*node, radius_slack(backward_radius[node.index()])))
+            .collect();
+
+        let mut highway_edges = Vec::new();
+
+        for node in self.settled.iter().rev() {
+            let label = &self.labels[node];
+            let edge = match label.parent {
+                Some(edge) => edge,
+                None => continue,
+            };
+
+            let distance = radius_slack(Decimetres.weight(network, edge));
+            let remaining = slack[node] - distance;
+
+            if remaining < 0 && label.cost > root_radius {
+                highway_edges.push(edge);
+            }
+
+            let parent_slack = slack
+                .get_mut(&network.edge_source(edge))
+                .expect("parent of a settled node is settled");
+            *parent_slack = (*parent_slack).min(remaining);
+        }
+
+        highway_edges
+    }
+}
+
+fn radius_slack(radius: u64) -> i64 {
+    radius.min(i64::MAX as u64) as i64
+}
@@ -0,0 +1,188 @@
+// Highway hierarchies, following "Engineering Highway Hierarchies" (Sanders, Schultes).
+//
+// Level 0 is the complete network. The edges of level l + 1 are the highway edges of level l:
+// an edge (u, v) is a highway edge if it lies on a shortest path (s, .., u, v, .., t) in level l
+// where v is outside the forward neighbourhood of s and u is outside the backward neighbourhood of t.
+// The neighbourhood of a node is the set of its `neighbourhood_size` closest nodes in that level.
+pub mod construction;
+pub mod neighbourhood;
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    algorithm::dijkstra::DijkstraDirection,
+    network::{file::Layout, EdgeId, Network, NodeId},
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Radius used for the top level, the search is never restricted there.
+pub const INFINITE_RADIUS: u64 = u64::MAX;
+
+#[derive(Debug, Clone, Copy)]
+pub struct HighwayParameters {
+    pub neighbourhood_size: usize,
+    pub max_levels: u8,
+}
+
+impl Default for HighwayParameters {
+    fn default() -> Self {
+        Self {
+            neighbourhood_size: 50,
+            max_levels: 5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HighwayHierarchy {
+    // For every edge the highest level it belongs to.
+    edge_level: Vec<u8>,
+    // Neighbourhood radii of every level below the top level, the top level is unrestricted.
+    levels: Vec<LevelRadii>,
+}
+
+impl Layout for HighwayHierarchy {
+    const LAYOUT: &'static str = "highway hierarchy";
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct LevelRadii {
+    forward: Vec<u64>,
+    backward: Vec<u64>,
+}
+
+impl HighwayHierarchy {
+    pub fn build<S: Network + Sync>(network: &S, parameters: HighwayParameters) -> Self {
+        let mut edge_level = vec![0u8; network.edge_len()];
+        let mut levels = Vec::new();
+
+        for level in 0..parameters.max_levels {
+            let nodes = level_nodes(network, &edge_level, level);
+            if nodes.is_empty() {
+                break;
+            }
+
+            let radii = LevelRadii {
+                forward: level_radii(
+                    network,
+                    &edge_level,
+                    level,
+                    &nodes,
+                    DijkstraDirection::Forward,
+                    parameters.neighbourhood_size,
+                ),
+                backward: level_radii(
+                    network,
+                    &edge_level,
+                    level,
+                    &nodes,
+                    DijkstraDirection::Backward,
+                    parameters.neighbourhood_size,
+                ),
+            };
+
+            let highway_edges = construction::select_highway_edges(
+                network,
+                &edge_level,
+                level,
+                &nodes,
+                &radii.forward,
+                &radii.backward,
+            );
+
+            if highway_edges.is_empty() {
+                break;
+            }
+
+            levels.push(radii);
+
+            for edge in highway_edges {
+                edge_level[edge.index()] = level + 1;
+            }
+        }
+
+        Self { edge_level, levels }
+    }
+
+    /// The highest level that contains edges.
+    pub fn top_level(&self) -> u8 {
+        self.levels.len() as u8
+    }
+
+    pub fn edge_level(&self, edge: EdgeId) -> u8 {
+        self.edge_level[edge]
+    }
+
+    /// For every node the highest level of its edges.
+    pub fn node_levels<S: Network>(&self, network: &S) -> Vec<u8> {
+        (0..network.nodes_len())
+            .map(NodeId::new)
+            .map(|node| {
+                network
+                    .outgoing_edges(node)
+                    .iter()
+                    .chain(network.incoming_edges(node).iter())
+                    .map(|edge| self.edge_level[*edge])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    pub fn forward_radius(&self, level: u8, node: NodeId) -> u64 {
+        self.levels
+            .get(level as usize)
+            .map_or(INFINITE_RADIUS, |radii| radii.forward[node])
+    }
+
+    pub fn backward_radius(&self, level: u8, node: NodeId) -> u64 {
+        self.levels
+            .get(level as usize)
+            .map_or(INFINITE_RADIUS, |radii| radii.backward[node])
+    }
+}
+
+/// Nodes that have at least one edge in `level`.
+fn level_nodes<S: Network>(network: &S, edge_level: &[u8], level: u8) -> Vec<NodeId> {
+    (0..network.nodes_len())
+        .map(NodeId::new)
+        .filter(|node| {
+            network
+                .outgoing_edges(*node)
+                .iter()
+                .chain(network.incoming_edges(*node).iter())
+                .any(|edge| edge_level[edge.index()] >= level)
+        })
+        .collect()
+}
+
+fn level_radii<S: Network + Sync>(
+    network: &S,
+    edge_level: &[u8],
+    level: u8,
+    nodes: &[NodeId],
+    direction: DijkstraDirection,
+    neighbourhood_size: usize,
+) -> Vec<u64> {
+    let mut radii = vec![INFINITE_RADIUS; network.nodes_len()];
+    let computed = nodes
+        .par_iter()
+        .map(|node| {
+            neighbourhood::neighbourhood_radius(
+                network,
+                edge_level,
+                level,
+                *node,
+                &direction,
+                neighbourhood_size,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for (node, radius) in nodes.iter().zip(computed) {
+        radii[*node] = radius;
+    }
+
+    radii
+}
@@ -0,0 +1,48 @@
+use crate::{
+    algorithm::{
+        dijkstra::DijkstraDirection,
+        weight::{Decimetres, Metric},
+    },
+    network::{Network, NodeId},
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+
+/// Distance in decimetres from `start` to its `size`-th closest node, only following edges of at least `level`.
+/// When fewer nodes are reachable the distance to the furthest reachable node is used.
+pub fn neighbourhood_radius<S: Network>(
+    network: &S,
+    edge_level: &[u8],
+    level: u8,
+    start: NodeId,
+    direction: &DijkstraDirection,
+    size: usize,
+) -> u64 {
+    let mut settled = HashSet::new();
+    let mut heap = BinaryHeap::new();
+    let mut radius = 0;
+
+    heap.push(Reverse((0u64, start)));
+
+    while let Some(Reverse((cost, node))) = heap.pop() {
+        if !settled.insert(node) {
+            continue;
+        }
+
+        radius = cost;
+        if settled.len() >= size {
+            break;
+        }
+
+        for (neighbour, edge) in direction.neighbours(node, network) {
+            if edge_level[edge.index()] >= level && !settled.contains(&neighbour) {
+                let cost = cost + Decimetres.weight(network, edge);
+                heap.push(Reverse((cost, neighbour)));
+            }
+        }
+    }
+
+    radius
+}
@@ -0,0 +1,234 @@
+// Construction of a highway hierarchy on a small network, compared with the definitions of the
+// neighbourhoods and highway edges evaluated by brute force on the shortest distances between all
+// nodes of every level.
+use super::{HighwayHierarchy, HighwayParameters};
+use crate::network::{generate, EdgeId, LiteNetwork, LiteNetworkBuilder, Network, NodeId};
+use std::collections::HashSet;
+
+const PARAMETERS: HighwayParameters = HighwayParameters {
+    neighbourhood_size: 5,
+    max_levels: 4,
+};
+
+/// A random geometric network in which every third road is one-way, so forward and backward
+/// neighbourhoods differ.
+fn network() -> LiteNetwork {
+    let full = generate::random_geometric(LiteNetworkBuilder::new(), 80, 1000.0, 200.0, 5);
+    let locations = (0..full.nodes_len())
+        .map(|node| full.node_location(NodeId::new(node)))
+        .map(|location| (location.x, location.y))
+        .collect::<Vec<_>>();
+    let edges = (0..full.edge_len())
+        .map(EdgeId::new)
+        .filter(|edge| {
+            full.edge_object_id(*edge) % 3 != 0 || full.edge_source(*edge) < full.edge_target(*edge)
+        })
+        .map(|edge| {
+            let (source, target) = (full.edge_source(edge), full.edge_target(edge));
+            (source.index(), target.index(), full.edge_distance(edge))
+        })
+        .collect::<Vec<_>>();
+
+    let network = generate::edge_list(LiteNetworkBuilder::new(), &locations, &edges);
+    assert!((0..network.edge_len()).all(|edge| network.edge_decimetres(EdgeId::new(edge)) > 0));
+    network
+}
+
+/// The edges of `level` and the nodes they connect.
+fn level_graph(
+    network: &LiteNetwork,
+    hierarchy: &HighwayHierarchy,
+    level: u8,
+) -> (Vec<EdgeId>, Vec<NodeId>) {
+    let edges = (0..network.edge_len())
+        .map(EdgeId::new)
+        .filter(|edge| hierarchy.edge_level(*edge) >= level)
+        .collect::<Vec<_>>();
+    let mut nodes = edges
+        .iter()
+        .flat_map(|edge| [network.edge_source(*edge), network.edge_target(*edge)])
+        .collect::<Vec<_>>();
+    nodes.sort_unstable();
+    nodes.dedup();
+    (edges, nodes)
+}
+
+/// Shortest distances between all nodes over `edges`, by Floyd-Warshall.
+fn all_distances(network: &LiteNetwork, edges: &[EdgeId]) -> Vec<Vec<Option<u64>>> {
+    let n = network.nodes_len();
+    let mut distances = vec![vec![None; n]; n];
+    for (node, row) in distances.iter_mut().enumerate() {
+        row[node] = Some(0);
+    }
+    for edge in edges {
+        let (source, target) = (network.edge_source(*edge), network.edge_target(*edge));
+        let weight = u64::from(network.edge_decimetres(*edge));
+        shorten(&mut distances[source.index()][target.index()], weight);
+    }
+
+    for via in 0..n {
+        let from_via = distances[via].clone();
+        for row in &mut distances {
+            let first = match row[via] {
+                Some(first) => first,
+                None => continue,
+            };
+            for (distance, second) in row.iter_mut().zip(&from_via) {
+                if let Some(second) = second {
+                    shorten(distance, first + second);
+                }
+            }
+        }
+    }
+    distances
+}
+
+/// Sets `distance` to `shorter` when that is shorter.
+fn shorten(distance: &mut Option<u64>, shorter: u64) {
+    if distance.is_none_or(|distance| shorter < distance) {
+        *distance = Some(shorter);
+    }
+}
+
+/// The number of shortest paths from `source` to every node over `edges`.
+fn path_counts(
+    network: &LiteNetwork,
+    edges: &[EdgeId],
+    distances: &[Vec<Option<u64>>],
+    source: NodeId,
+) -> Vec<u64> {
+    let from_source = &distances[source.index()];
+    let mut order = (0..network.nodes_len())
+        .filter_map(|node| Some((from_source[node]?, node)))
+        .collect::<Vec<_>>();
+    order.sort_unstable();
+
+    let mut counts = vec![0u64; network.nodes_len()];
+    counts[source.index()] = 1;
+    for (distance, node) in order {
+        for edge in edges
+            .iter()
+            .filter(|edge| network.edge_source(**edge).index() == node)
+        {
+            let target = network.edge_target(*edge).index();
+            if Some(distance + u64::from(network.edge_decimetres(*edge))) == from_source[target] {
+                counts[target] += counts[node];
+            }
+        }
+    }
+    counts
+}
+
+/// Distance to the `size`-th closest of `distances`, or to the furthest when there are fewer.
+fn radius<I: Iterator<Item = Option<u64>>>(distances: I, size: usize) -> u64 {
+    let mut reachable = distances.flatten().collect::<Vec<_>>();
+    reachable.sort_unstable();
+    reachable[size.min(reachable.len()) - 1]
+}
+
+#[test]
+fn neighbourhood_radii_match_brute_force() {
+    let network = network();
+    let hierarchy = HighwayHierarchy::build(&network, PARAMETERS);
+    assert!(hierarchy.top_level() >= 2);
+
+    for level in 0..hierarchy.top_level() {
+        let (edges, nodes) = level_graph(&network, &hierarchy, level);
+        let distances = all_distances(&network, &edges);
+
+        for node in nodes {
+            let forward = radius(
+                distances[node.index()].iter().copied(),
+                PARAMETERS.neighbourhood_size,
+            );
+            let backward = radius(
+                distances.iter().map(|row| row[node.index()]),
+                PARAMETERS.neighbourhood_size,
+            );
+            assert_eq!(hierarchy.forward_radius(level, node), forward, "{:?}", node);
+            assert_eq!(
+                hierarchy.backward_radius(level, node),
+                backward,
+                "{:?}",
+                node
+            );
+        }
+    }
+}
+
+/// An edge (u, v) of a level is needed in the next level when it lies on a shortest path
+/// (s, .., u, v, .., t) where v is outside the forward neighbourhood of s and u is outside the
+/// backward neighbourhood of t. Every selected edge is needed for some path, and every edge that
+/// is needed for a path that is the only shortest path is selected.
+#[test]
+fn highway_edges_are_closed_under_shortest_paths() {
+    let network = network();
+    let hierarchy = HighwayHierarchy::build(&network, PARAMETERS);
+
+    for level in 0..hierarchy.top_level() {
+        let (edges, nodes) = level_graph(&network, &hierarchy, level);
+        let distances = all_distances(&network, &edges);
+        let size = PARAMETERS.neighbourhood_size;
+        let forward = |node: NodeId| radius(distances[node.index()].iter().copied(), size);
+        let backward = |node: NodeId| radius(distances.iter().map(|row| row[node.index()]), size);
+
+        let mut needed = HashSet::new();
+        let mut needed_by_only_path = HashSet::new();
+        for source in &nodes {
+            let counts = path_counts(&network, &edges, &distances, *source);
+            let from_source = &distances[source.index()];
+            let source_radius = forward(*source);
+
+            for target in &nodes {
+                let total = match from_source[target.index()] {
+                    Some(total) => total,
+                    None => continue,
+                };
+                let target_radius = backward(*target);
+
+                for edge in &edges {
+                    let (u, v) = (network.edge_source(*edge), network.edge_target(*edge));
+                    let (to_u, to_v) = (from_source[u.index()], from_source[v.index()]);
+                    let (from_u, from_v) = (
+                        distances[u.index()][target.index()],
+                        distances[v.index()][target.index()],
+                    );
+                    let on_path = match (to_u, from_v) {
+                        (Some(to_u), Some(from_v)) => {
+                            to_u + u64::from(network.edge_decimetres(*edge)) + from_v == total
+                        }
+                        _ => false,
+                    };
+
+                    if on_path && to_v.unwrap() > source_radius && from_u.unwrap() > target_radius {
+                        needed.insert(*edge);
+                        if counts[target.index()] == 1 {
+                            needed_by_only_path.insert(*edge);
+                        }
+                    }
+                }
+            }
+        }
+
+        let selected = edges
+            .iter()
+            .copied()
+            .filter(|edge| hierarchy.edge_level(*edge) > level)
+            .collect::<HashSet<_>>();
+        assert!(!selected.is_empty());
+        assert!(
+            selected.is_subset(&needed),
+            "level {} selects {:?}, which no shortest path needs",
+            level,
+            selected.difference(&needed).collect::<Vec<_>>()
+        );
+        assert!(
+            needed_by_only_path.is_subset(&selected),
+            "level {} misses {:?}",
+            level,
+            needed_by_only_path
+                .difference(&selected)
+                .collect::<Vec<_>>()
+        );
+    }
+}
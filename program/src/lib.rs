@@ -0,0 +1,26 @@
+//! Route planning on the Dutch road network (Nationaal Wegenbestand).
+//!
+//! A `LiteNetwork` is built from the Wegvakken shapefile by the functions in `preprocess`, which
+//! store it in a versioned file (`network::file`) and read it back while it is not stale. The
+//! network and the preprocessing of the algorithms are `Writeable` to such files. Other sources,
+//! or networks made in memory, feed junctions and roads to a `NetworkBuilder`. The algorithms in
+//! `algorithm` find paths on it, either between two nodes (`PathAlgorithm`) or between all pairs
+//! of a set of nodes (`ManyToManyAlgorithm`). Paths cost whole decimetres, other weights of the
+//! edges are `Metric`s in `algorithm::weight`.
+//!
+//! A `MappedNetwork` is read in place from a memory-mapped file instead, so opening it does not
+//! depend on the size of the network.
+pub mod algorithm;
+pub mod contraction;
+mod f32_wrapper;
+pub mod highway;
+pub mod network;
+pub mod node_routing;
+pub mod preprocess;
+pub mod region;
+
+pub use algorithm::{EdgePath, ManyManyErrors, ManyToManyAlgorithm, PathAlgorithm};
+pub use network::{
+    builder::NetworkBuilder, EdgeId, LiteNetwork, LiteNetworkBuilder, Network, NodeCoord, NodeId,
+    Writeable,
+};
@@ -1,38 +1,40 @@
-use super::{consts::*, utils::*, EdgeId, Network, NodeId};
+// A network stored as an array of structs: every node and every edge is one record, where
+// `LiteNetwork` keeps a separate array per field. Both implement `Network` the same way, so the
+// layouts can be compared on the same queries.
+use super::{
+    builder::{DistanceError, NetworkBuilder},
+    metadata::MetadataTable,
+    network_lite::stored_distance,
+    EdgeId, EdgeMetadata, Network, NodeCoord, NodeId,
+};
 use serde::{Deserialize, Serialize};
-use shapefile::{reader::ShapeRecordIterator, Polyline};
-use std::{collections::HashMap, fs::File, io::BufReader};
-#[derive(Debug, Serialize, Deserialize)]
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AoSNetwork {
-    pub node_map: HashMap<usize, usize>,
-    pub nodes: Vec<Node>,
-    pub edges: Vec<Vec<Edge>>,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    metadata: MetadataTable,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Node {
-    id: usize,
-    junction_id: usize,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node {
+    junction_id: u32,
+    location: NodeCoord,
+    outgoing: Vec<EdgeId>,
+    incoming: Vec<EdgeId>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Edge {
-    id: usize,
-    related: Option<usize>,
-    pub distance: f32,
-    target: usize,
-}
-
-#[derive(Debug)]
-pub struct BuildNode {
-    pub junction_id: usize,
-}
-#[derive(Debug)]
-pub struct BuildEdge {
-    pub source_node: usize,
-    pub target_node: usize,
-    pub distance: f32,
-    pub direction: RoadDirection,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Edge {
+    object_id: u32,
+    source: NodeId,
+    target: NodeId,
+    distance: f32,
+    speed: u8,
+    lanes: u8,
+    // Row in the metadata table.
+    metadata: Option<u32>,
 }
 
 impl Network for AoSNetwork {
@@ -40,134 +42,181 @@ impl Network for AoSNetwork {
         self.nodes.len()
     }
 
-    fn junction_id(&self, id: NodeId) -> usize {
+    fn junction_id(&self, id: NodeId) -> u32 {
         self.nodes[id].junction_id
     }
 
-    fn outgoing_edges(&self, _id: NodeId) -> &Vec<EdgeId> {
-        todo!()
+    fn outgoing_edges(&self, id: NodeId) -> &[EdgeId] {
+        &self.nodes[id].outgoing
+    }
+
+    fn incoming_edges(&self, id: NodeId) -> &[EdgeId] {
+        &self.nodes[id].incoming
+    }
+
+    fn edge_len(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn edge_source(&self, id: EdgeId) -> NodeId {
+        self.edges[id].source
+    }
+
+    fn edge_target(&self, id: EdgeId) -> NodeId {
+        self.edges[id].target
     }
 
-    fn incoming_edges(&self, _id: NodeId) -> &Vec<EdgeId> {
-        todo!()
+    fn edge_object_id(&self, id: EdgeId) -> u32 {
+        self.edges[id].object_id
     }
 
-    fn edge_source(&self, _id: EdgeId) -> NodeId {
-        todo!()
+    fn edge_distance(&self, id: EdgeId) -> f32 {
+        self.edges[id].distance
     }
 
-    fn edge_target(&self, _id: EdgeId) -> NodeId {
-        todo!()
+    fn edge_speed(&self, id: EdgeId) -> u8 {
+        self.edges[id].speed
     }
 
-    fn edge_object_id(&self, _id: EdgeId) -> usize {
-        todo!()
+    fn edge_lanes(&self, id: EdgeId) -> u8 {
+        self.edges[id].lanes
     }
 
-    fn edge_distance(&self, _id: EdgeId) -> f32 {
-        todo!()
+    fn edge_metadata(&self, id: EdgeId) -> Option<EdgeMetadata<'_>> {
+        self.edges[id].metadata.map(|row| self.metadata.get(row))
     }
 
-    fn node_location(&self, _id: NodeId) -> super::NodeCoord {
-        todo!()
+    fn node_location(&self, id: NodeId) -> NodeCoord {
+        self.nodes[id].location
+    }
+}
+
+impl Default for AoSNetwork {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl AoSNetwork {
     pub fn new() -> Self {
         Self {
-            node_map: HashMap::new(),
             nodes: Vec::new(),
             edges: Vec::new(),
+            metadata: MetadataTable::new(),
         }
     }
 
-    pub fn add_node(&mut self, node: BuildNode) -> usize {
-        if let Some(id) = self.node_map.get(&node.junction_id) {
-            *id
-        } else {
-            let id = self.nodes.len();
+    /// Copies any network with the same node and edge ids.
+    pub fn from_network<S: Network>(network: &S) -> Self {
+        let mut copy = Self::new();
+        // The row of the metadata of every road, which all of its edges share.
+        let mut rows = HashMap::new();
+        for node in (0..network.nodes_len()).map(NodeId::new) {
+            copy.push_node(network.junction_id(node), network.node_location(node));
+        }
+        for edge in (0..network.edge_len()).map(EdgeId::new) {
+            copy.push_edge(
+                network.edge_object_id(edge),
+                network.edge_source(edge),
+                network.edge_target(edge),
+                network.edge_distance(edge),
+                network.edge_speed(edge),
+                network.edge_lanes(edge),
+            );
+            if let Some(metadata) = network.edge_metadata(edge) {
+                let row = *rows
+                    .entry(metadata)
+                    .or_insert_with(|| copy.metadata.add(&metadata));
+                copy.edges[edge].metadata = Some(row);
+            }
+        }
+        copy
+    }
 
-            self.nodes.push(Node {
-                id,
-                junction_id: node.junction_id,
-            });
-            self.edges.push(Vec::new());
-            self.node_map.insert(node.junction_id, id);
+    fn push_node(&mut self, junction_id: u32, location: NodeCoord) -> NodeId {
+        let id = NodeId::new(self.nodes.len());
+        self.nodes.push(Node {
+            junction_id,
+            location,
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
+        });
+        id
+    }
 
-            id
-        }
+    fn push_edge(
+        &mut self,
+        object_id: u32,
+        source: NodeId,
+        target: NodeId,
+        distance: f32,
+        speed: u8,
+        lanes: u8,
+    ) -> EdgeId {
+        let id = EdgeId::new(self.edges.len());
+        self.edges.push(Edge {
+            object_id,
+            source,
+            target,
+            distance,
+            speed,
+            lanes,
+            metadata: None,
+        });
+        self.nodes[source].outgoing.push(id);
+        self.nodes[target].incoming.push(id);
+        id
     }
+}
 
-    pub fn add_edge(&mut self, edge: BuildEdge) {
-        let source_node = &edge.source_node;
-        let target_node = &edge.target_node;
-
-        match edge.direction {
-            RoadDirection::BOTH => {
-                let source_edge_id = self.edges[*source_node].len();
-                let target_edge_id = self.edges[*target_node].len();
-                self.edges[*source_node].push(Edge {
-                    id: source_edge_id,
-                    related: Some(target_edge_id),
-                    distance: edge.distance,
-                    target: *target_node,
-                });
-                self.edges[*target_node].push(Edge {
-                    id: target_edge_id,
-                    distance: edge.distance,
-                    related: Some(source_edge_id),
-                    target: *source_node,
-                });
-            }
-            RoadDirection::WITH => {
-                let source_edge_id = self.edges[*source_node].len();
-                self.edges[*source_node].push(Edge {
-                    id: source_edge_id,
-                    related: None,
-                    distance: edge.distance,
-                    target: *target_node,
-                });
-            }
-            RoadDirection::AGAINST => {
-                let target_edge_id = self.edges[*target_node].len();
-                self.edges[*target_node].push(Edge {
-                    id: target_edge_id,
-                    distance: edge.distance,
-                    related: None,
-                    target: *source_node,
-                });
-            }
-        }
+/// Builds an `AoSNetwork`.
+#[derive(Debug, Default)]
+pub struct AoSNetworkBuilder {
+    network: AoSNetwork,
+    junctions: HashMap<u32, NodeId>,
+}
+
+impl AoSNetworkBuilder {
+    pub fn new() -> Self {
+        Self::default()
     }
 }
 
-impl From<ShapeRecordIterator<BufReader<File>, Polyline>> for AoSNetwork {
-    fn from(shapes: ShapeRecordIterator<BufReader<File>, Polyline>) -> Self {
-        let mut network = AoSNetwork::new();
-
-        for entry in shapes {
-            let (shape, record) = entry.unwrap();
-            let direction: RoadDirection =
-                get_character(&record, DIRECTION).unwrap().parse().unwrap();
-            let node_start = get_numeric(&record, NODE_START).unwrap() as usize;
-            let node_end = get_numeric(&record, NODE_END).unwrap() as usize;
-
-            let node_start = network.add_node(BuildNode {
-                junction_id: node_start,
-            });
-            let node_end = network.add_node(BuildNode {
-                junction_id: node_end,
-            });
-
-            network.add_edge(BuildEdge {
-                source_node: node_start,
-                target_node: node_end,
-                direction,
-                distance: calculate_distance(&shape),
-            });
+impl NetworkBuilder for AoSNetworkBuilder {
+    type Network = AoSNetwork;
+
+    fn add_junction(&mut self, junction_id: u32, location: NodeCoord) -> NodeId {
+        let network = &mut self.network;
+        *self
+            .junctions
+            .entry(junction_id)
+            .or_insert_with(|| network.push_node(junction_id, location))
+    }
+
+    fn add_edge(
+        &mut self,
+        object_id: u32,
+        source: NodeId,
+        target: NodeId,
+        distance: f32,
+        speed: u8,
+        lanes: u8,
+    ) -> Result<EdgeId, DistanceError> {
+        // Stored as metres, but only the distances `LiteNetwork` can store are accepted.
+        stored_distance(distance)?;
+        Ok(self
+            .network
+            .push_edge(object_id, source, target, distance, speed, lanes))
+    }
+
+    fn add_metadata(&mut self, edges: &[EdgeId], metadata: &EdgeMetadata) {
+        let row = self.network.metadata.add(metadata);
+        for edge in edges {
+            self.network.edges[*edge].metadata = Some(row);
         }
+    }
 
-        network
+    fn build(self) -> AoSNetwork {
+        self.network
     }
 }
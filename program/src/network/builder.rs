@@ -0,0 +1,81 @@
+// Building networks independent of the input format: an importer reads junctions and roads and
+// feeds them to a `NetworkBuilder`, which stores them in the layout of its network. See `nwb` for
+// the importer of the Wegvakken shapefile.
+use super::{utils::RoadDirection, EdgeId, EdgeMetadata, Network, NodeCoord, NodeId};
+use std::{error::Error, fmt};
+
+/// A road between two junctions that were added to the builder.
+#[derive(Debug)]
+pub struct Road {
+    /// Id of the road in the source data, the wegvak id for the NWB.
+    pub object_id: u32,
+    pub start: NodeId,
+    pub end: NodeId,
+    /// Length in metres.
+    pub distance: f32,
+    /// Speed in km/h.
+    pub speed: u8,
+    /// Number of lanes in every direction, 0 when it is not known.
+    pub lanes: u8,
+    pub direction: RoadDirection,
+}
+
+/// A distance in metres that the network can not store, because it is negative, not a number or
+/// too long.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceError {
+    pub distance: f32,
+}
+
+impl fmt::Display for DistanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "distance {} can not be stored", self.distance)
+    }
+}
+
+impl Error for DistanceError {}
+
+pub trait NetworkBuilder {
+    type Network: Network;
+
+    /// Adds the node of a junction, or returns its node when the junction was already added.
+    fn add_junction(&mut self, junction_id: u32, location: NodeCoord) -> NodeId;
+
+    /// Adds a single edge from `source` to `target`, nothing is added when the network can not
+    /// store `distance`.
+    fn add_edge(
+        &mut self,
+        object_id: u32,
+        source: NodeId,
+        target: NodeId,
+        distance: f32,
+        speed: u8,
+        lanes: u8,
+    ) -> Result<EdgeId, DistanceError>;
+
+    /// Adds an edge for every direction the road can be driven in.
+    fn add_road(&mut self, road: &Road) -> Result<Vec<EdgeId>, DistanceError> {
+        let (start, end) = (road.start, road.end);
+        let mut add = |source, target| {
+            self.add_edge(
+                road.object_id,
+                source,
+                target,
+                road.distance,
+                road.speed,
+                road.lanes,
+            )
+        };
+
+        match road.direction {
+            RoadDirection::BOTH => Ok(vec![add(start, end)?, add(end, start)?]),
+            RoadDirection::WITH => Ok(vec![add(start, end)?]),
+            RoadDirection::AGAINST => Ok(vec![add(end, start)?]),
+        }
+    }
+
+    /// Adds the metadata of the road of `edges`.
+    fn add_metadata(&mut self, edges: &[EdgeId], metadata: &EdgeMetadata);
+
+    fn build(self) -> Self::Network;
+}
@@ -1,3 +1,14 @@
-pub const NODE_START: &'static str = "JTE_ID_BEG";
-pub const NODE_END: &'static str = "JTE_ID_END";
-pub const DIRECTION: &'static str = "RIJRICHTNG";
\ No newline at end of file
+pub const NODE_START: &str = "JTE_ID_BEG";
+pub const NODE_END: &str = "JTE_ID_END";
+pub const DIRECTION: &str = "RIJRICHTNG";
+pub const WEGVAK_ID: &str = "WVK_ID";
+pub const STREET_NAME: &str = "STT_NAAM";
+pub const ROAD_NUMBER: &str = "WEGNUMMER";
+pub const MUNICIPALITY: &str = "GME_NAAM";
+pub const ROAD_TYPE: &str = "BST_CODE";
+
+// Fields of the wegkenmerken (weggeg) shapefiles, every record gives a value to a part of a wegvak.
+pub const PART_START: &str = "BEGAFSTAND";
+pub const PART_END: &str = "ENDAFSTAND";
+pub const SIDE: &str = "KANTCODE";
+pub const DESCRIPTION: &str = "OMSCHR";
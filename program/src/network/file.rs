@@ -0,0 +1,183 @@
+// File format of preprocessed networks.
+//
+// A network file starts with `MAGIC` and `FORMAT_VERSION`, followed by a header that records how
+// the network was built and then the network itself, all encoded with bincode. The header is
+// compared before the network is deserialized, so a file written by another version of the
+// program, for another network type, from other source data or with other options is reported as
+// stale instead of being read as garbage.
+//
+// The preprocessing of an algorithm is stored the same way, with the header of the network it is
+// built from, so it is stale whenever that network is.
+use crate::preprocess::BuildOptions;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+const MAGIC: [u8; 8] = *b"DUTCHRD\0";
+
+/// Increase when the serialized layout of a network or of the header changes.
+pub const FORMAT_VERSION: u32 = 12;
+
+/// A type that is stored in network files.
+pub trait Layout {
+    /// Names the serialized layout of the type in the header, so a file of another type is stale.
+    /// It is not the type name, which can differ between compiler versions.
+    const LAYOUT: &'static str;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileHeader {
+    // `Layout::LAYOUT` of the network, other types have another layout.
+    layout: String,
+    source: Source,
+    options: BuildOptions,
+}
+
+/// The source files a network is built from. Reading a network only compares the sizes and
+/// modification times of the files, the hash of their contents is computed when it is built.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Source {
+    pub hash: u64,
+    pub files: Vec<FileStamp>,
+}
+
+/// Size and modification time of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+impl FileStamp {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path).map_err(|error| format!("{:?}: {}", path, error))?;
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+}
+
+/// What the source in the header is compared with when a file is read.
+#[derive(Debug, Clone, Copy)]
+pub enum SourceCheck<'a> {
+    /// The source files are not known, so not checked.
+    Unknown,
+    /// The stamps of the source files as they are now.
+    Files(&'a [FileStamp]),
+    /// The source of the network the file is built from.
+    Network(&'a Source),
+}
+
+/// Result of reading a network file that is valid but might not be up to date.
+#[derive(Debug)]
+pub enum NetworkFile<S> {
+    Current(S),
+    /// The file was built differently, with the reason why.
+    Stale(String),
+}
+
+/// Writes `network` with a header saying it is built with `options` from `source`.
+pub fn write_network<S, P>(
+    network: &S,
+    path: P,
+    options: &BuildOptions,
+    source: &Source,
+) -> Result<(), Box<dyn Error>>
+where
+    S: Layout + Serialize,
+    P: AsRef<Path>,
+{
+    let header = FileHeader {
+        layout: S::LAYOUT.to_string(),
+        source: source.clone(),
+        options: options.clone(),
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    bincode::serialize_into(&mut writer, &header)?;
+    bincode::serialize_into(&mut writer, network)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a network written by `write_network`, the network is only deserialized when it is
+/// built the way it would be built now. A file without a header, such as a network written before
+/// files had one, is stale.
+pub fn read_network<S, P>(
+    path: P,
+    options: &BuildOptions,
+    source: SourceCheck,
+) -> Result<NetworkFile<S>, Box<dyn Error>>
+where
+    S: Layout + DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let mut reader = BufReader::new(File::open(path.as_ref())?);
+    let header = match read_header(&mut reader)? {
+        NetworkFile::Current(header) => header,
+        NetworkFile::Stale(reason) => return Ok(NetworkFile::Stale(reason)),
+    };
+
+    if header.layout != S::LAYOUT {
+        return Ok(NetworkFile::Stale(format!(
+            "contains a {}, expected a {}",
+            header.layout,
+            S::LAYOUT
+        )));
+    }
+    if let Some(difference) = header.options.difference(options) {
+        return Ok(NetworkFile::Stale(format!("built with {}", difference)));
+    }
+    let source_changed = match source {
+        SourceCheck::Unknown => false,
+        SourceCheck::Files(files) => header.source.files != files,
+        SourceCheck::Network(source) => header.source != *source,
+    };
+    if source_changed {
+        return Ok(NetworkFile::Stale("the source data changed".to_string()));
+    }
+
+    Ok(NetworkFile::Current(bincode::deserialize_from(reader)?))
+}
+
+/// The source of the network in the file at `path`, which must be current.
+pub fn read_source<P: AsRef<Path>>(path: P) -> Result<Source, Box<dyn Error>> {
+    let mut reader = BufReader::new(File::open(path.as_ref())?);
+    match read_header(&mut reader)? {
+        NetworkFile::Current(header) => Ok(header.source),
+        NetworkFile::Stale(reason) => {
+            Err(format!("{:?} is stale ({})", path.as_ref(), reason).into())
+        }
+    }
+}
+
+/// The header of the file, which is stale when the file is of another version or has no header.
+fn read_header<R: Read>(mut reader: R) -> Result<NetworkFile<FileHeader>, Box<dyn Error>> {
+    let mut magic = [0; 8];
+    if reader.read_exact(&mut magic).is_err() || magic != MAGIC {
+        return Ok(NetworkFile::Stale(
+            "not a network file, or written before network files had a header".to_string(),
+        ));
+    }
+
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != FORMAT_VERSION {
+        return Ok(NetworkFile::Stale(format!(
+            "format version {}, expected {}",
+            version, FORMAT_VERSION
+        )));
+    }
+
+    Ok(NetworkFile::Current(bincode::deserialize_from(reader)?))
+}
@@ -0,0 +1,161 @@
+// Synthetic networks for tests and benchmarks, made with any `NetworkBuilder`. Junction ids are
+// the node numbers in the order the junctions are added, and object ids the road numbers. All
+// roads have speed `SPEED` and an unknown number of lanes.
+use super::{
+    builder::{NetworkBuilder, Road},
+    utils::RoadDirection,
+    NodeCoord, NodeId,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::HashMap, convert::TryFrom};
+
+/// Speed in km/h of the generated roads.
+pub const SPEED: u8 = 50;
+
+/// A grid of `width` by `height` junctions `spacing` metres apart, with roads in both directions
+/// between neighbours. The junction in column `x` and row `y` is node `y * width + x`.
+pub fn grid<B: NetworkBuilder>(
+    mut builder: B,
+    width: usize,
+    height: usize,
+    spacing: f32,
+) -> B::Network {
+    let mut nodes = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let location = NodeCoord {
+                x: x as f32 * spacing,
+                y: y as f32 * spacing,
+            };
+            nodes.push(builder.add_junction(number(nodes.len()), location));
+        }
+    }
+
+    let mut roads = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let node = nodes[y * width + x];
+            let right = (x + 1 < width).then(|| nodes[y * width + x + 1]);
+            let down = (y + 1 < height).then(|| nodes[(y + 1) * width + x]);
+
+            for neighbour in right.into_iter().chain(down) {
+                builder
+                    .add_road(&Road {
+                        object_id: number(roads),
+                        start: node,
+                        end: neighbour,
+                        distance: spacing,
+                        speed: SPEED,
+                        lanes: 0,
+                        direction: RoadDirection::BOTH,
+                    })
+                    .expect("spacing can not be stored");
+                roads += 1;
+            }
+        }
+    }
+
+    builder.build()
+}
+
+/// `nodes` junctions at random locations in a square of `side` metres, with roads in both
+/// directions between junctions less than `radius` apart. Distances are the straight lines, which
+/// the builder rounds to the nearest decimetre as it does for imported roads.
+pub fn random_geometric<B: NetworkBuilder>(
+    mut builder: B,
+    nodes: usize,
+    side: f32,
+    radius: f32,
+    seed: u64,
+) -> B::Network {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let locations = (0..nodes)
+        .map(|_| NodeCoord {
+            x: rng.gen_range(0.0..side),
+            y: rng.gen_range(0.0..side),
+        })
+        .collect::<Vec<_>>();
+    let ids = locations
+        .iter()
+        .enumerate()
+        .map(|(node, location)| builder.add_junction(number(node), *location))
+        .collect::<Vec<_>>();
+
+    // Only junctions in the same or a neighbouring cell of `radius` wide can be close enough.
+    let cell = |location: &NodeCoord| {
+        (
+            (location.x / radius).floor() as i64,
+            (location.y / radius).floor() as i64,
+        )
+    };
+    let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (node, location) in locations.iter().enumerate() {
+        cells.entry(cell(location)).or_default().push(node);
+    }
+
+    let mut roads = 0;
+    for (node, location) in locations.iter().enumerate() {
+        let (x, y) = cell(location);
+        let mut neighbours = (x - 1..=x + 1)
+            .flat_map(|x| (y - 1..=y + 1).map(move |y| (x, y)))
+            .filter_map(|cell| cells.get(&cell))
+            .flatten()
+            .filter(|other| **other > node)
+            .copied()
+            .collect::<Vec<_>>();
+        neighbours.sort_unstable();
+
+        for other in neighbours {
+            let distance = location.distance(&locations[other]);
+            if distance < radius {
+                builder
+                    .add_road(&Road {
+                        object_id: number(roads),
+                        start: ids[node],
+                        end: ids[other],
+                        distance,
+                        speed: SPEED,
+                        lanes: 0,
+                        direction: RoadDirection::BOTH,
+                    })
+                    .expect("radius can not be stored");
+                roads += 1;
+            }
+        }
+    }
+
+    builder.build()
+}
+
+/// Junctions at `locations` and a one-way road for every `(source, target, distance)`, where the
+/// source and target are indices into `locations`.
+pub fn edge_list<B: NetworkBuilder>(
+    mut builder: B,
+    locations: &[(f32, f32)],
+    edges: &[(usize, usize, f32)],
+) -> B::Network {
+    let nodes = locations
+        .iter()
+        .enumerate()
+        .map(|(node, (x, y))| builder.add_junction(number(node), NodeCoord { x: *x, y: *y }))
+        .collect::<Vec<NodeId>>();
+
+    for (road, (source, target, distance)) in edges.iter().enumerate() {
+        builder
+            .add_edge(
+                number(road),
+                nodes[*source],
+                nodes[*target],
+                *distance,
+                SPEED,
+                0,
+            )
+            .expect("distance can not be stored");
+    }
+
+    builder.build()
+}
+
+fn number(index: usize) -> u32 {
+    u32::try_from(index).expect("too many junctions or roads for 32 bits")
+}
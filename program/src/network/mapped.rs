@@ -0,0 +1,448 @@
+// A network stored as flat arrays that are read in place from a memory-mapped file.
+//
+// The file starts with a header, followed by the arrays in the order of `Layout`. All elements
+// are 4 or 8 bytes and aligned to 4 bytes, so all arrays are aligned when the file is mapped at a
+// page boundary. Only the speeds, lanes and texts are single bytes, they come last. Distances are
+// whole decimetres, as `LiteNetwork` stores them. Adjacency is
+// stored as compressed sparse rows: the outgoing edges of node `n` are
+// `outgoing_edges[outgoing_offsets[n]..outgoing_offsets[n + 1]]`, and the same for incoming.
+//
+// The metadata of roads is a `MetadataRow` for every edge, whose texts are numbers of distinct
+// texts: text `t` is `text[text_offsets[t]..text_offsets[t + 1]]` in UTF-8.
+//
+// Opening maps the file, checks the header and reads the offsets and ids once, so that a damaged
+// file is an error there rather than a panic on a later lookup. The other arrays are only read
+// when they are used. The arrays are in the byte order of the machine that wrote them.
+use super::{EdgeId, EdgeMetadata, Network, NodeCoord, NodeId, DECIMETRES_PER_METRE};
+use bytemuck::{Pod, Zeroable};
+use memmap2::Mmap;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    mem::size_of,
+    ops::Range,
+    path::Path,
+};
+
+const MAGIC: [u8; 8] = *b"DUTCHMAP";
+const VERSION: u32 = 7;
+const ENDIANNESS: u32 = 0x0102_0304;
+
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct MapHeader {
+    magic: [u8; 8],
+    version: u32,
+    endianness: u32,
+    nodes: u64,
+    edges: u64,
+    texts: u64,
+    text_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct MetadataRow {
+    // Numbers of the street name, road number, municipality and road type, the first is
+    // `NO_METADATA` for edges without metadata.
+    texts: [u32; 4],
+}
+
+const NO_METADATA: u32 = u32::MAX;
+
+/// Byte ranges of the arrays in the file.
+#[derive(Debug, Clone)]
+struct Layout {
+    junction_id: Range<usize>,
+    location: Range<usize>,
+    outgoing_offsets: Range<usize>,
+    outgoing_edges: Range<usize>,
+    incoming_offsets: Range<usize>,
+    incoming_edges: Range<usize>,
+    source: Range<usize>,
+    target: Range<usize>,
+    object_id: Range<usize>,
+    distance: Range<usize>,
+    metadata: Range<usize>,
+    text_offsets: Range<usize>,
+    speed: Range<usize>,
+    lanes: Range<usize>,
+    text: Range<usize>,
+}
+
+impl Layout {
+    /// `None` when a count of the header does not fit in the ids and offsets, or the arrays do not
+    /// fit in memory.
+    fn new(header: &MapHeader) -> Option<Self> {
+        let count = |count: u64| u32::try_from(count).ok().map(|count| count as usize);
+        let (nodes, edges) = (count(header.nodes)?, count(header.edges)?);
+        let (texts, text_bytes) = (count(header.texts)?, count(header.text_bytes)?);
+        let mut end = size_of::<MapHeader>();
+        let mut next = |len: usize, element: usize| {
+            let start = end;
+            end = len.checked_mul(element)?.checked_add(start)?;
+            Some(start..end)
+        };
+
+        Some(Self {
+            junction_id: next(nodes, size_of::<u32>())?,
+            location: next(nodes, size_of::<NodeCoord>())?,
+            outgoing_offsets: next(nodes.checked_add(1)?, size_of::<u32>())?,
+            outgoing_edges: next(edges, size_of::<EdgeId>())?,
+            incoming_offsets: next(nodes.checked_add(1)?, size_of::<u32>())?,
+            incoming_edges: next(edges, size_of::<EdgeId>())?,
+            source: next(edges, size_of::<NodeId>())?,
+            target: next(edges, size_of::<NodeId>())?,
+            object_id: next(edges, size_of::<u32>())?,
+            distance: next(edges, size_of::<u32>())?,
+            metadata: next(edges, size_of::<MetadataRow>())?,
+            text_offsets: next(texts.checked_add(1)?, size_of::<u32>())?,
+            speed: next(edges, size_of::<u8>())?,
+            lanes: next(edges, size_of::<u8>())?,
+            text: next(text_bytes, size_of::<u8>())?,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.text.end
+    }
+}
+
+#[derive(Debug)]
+pub struct MappedNetwork {
+    map: Mmap,
+    layout: Layout,
+}
+
+impl MappedNetwork {
+    /// Maps the network file at `path`, and checks that its offsets and ids are in bounds.
+    ///
+    /// The file must not be changed while it is mapped.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        // Safety: the file is only read, and is not supposed to change while it is in use.
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header: MapHeader = map
+            .get(..size_of::<MapHeader>())
+            .map(bytemuck::pod_read_unaligned)
+            .filter(|header: &MapHeader| header.magic == MAGIC)
+            .ok_or_else(|| format!("{:?} is not a mapped network file", path))?;
+
+        if header.version != VERSION {
+            return Err(format!(
+                "{:?} has version {}, expected {}",
+                path, header.version, VERSION
+            )
+            .into());
+        }
+        if header.endianness != ENDIANNESS {
+            return Err(
+                format!("{:?} is written on a machine with another byte order", path).into(),
+            );
+        }
+
+        let layout = Layout::new(&header)
+            .ok_or_else(|| format!("{:?} has a header with too many elements", path))?;
+        if map.len() != layout.len() {
+            return Err(format!(
+                "{:?} has {} bytes, expected {}",
+                path,
+                map.len(),
+                layout.len()
+            )
+            .into());
+        }
+
+        let network = Self { map, layout };
+        network
+            .check()
+            .map_err(|error| format!("{:?} {}", path, error))?;
+        Ok(network)
+    }
+
+    /// Checks that rows of the adjacency and texts are within their arrays, and that the edges of
+    /// the adjacency and the nodes of the edges exist.
+    fn check(&self) -> Result<(), String> {
+        let (nodes, edges) = (self.nodes_len(), self.edge_len());
+        let layout = &self.layout;
+        check_offsets(
+            self.array(&layout.outgoing_offsets),
+            edges,
+            "outgoing edges",
+        )?;
+        check_offsets(
+            self.array(&layout.incoming_offsets),
+            edges,
+            "incoming edges",
+        )?;
+        check_offsets(self.array(&layout.text_offsets), layout.text.len(), "texts")?;
+        check_ids(self.array(&layout.outgoing_edges), edges, "outgoing edge")?;
+        check_ids(self.array(&layout.incoming_edges), edges, "incoming edge")?;
+        check_ids(self.array(&layout.source), nodes, "source node")?;
+        check_ids(self.array(&layout.target), nodes, "target node")
+    }
+
+    /// Writes any network in the layout that `open` maps.
+    pub fn write<S: Network, P: AsRef<Path>>(network: &S, path: P) -> Result<(), Box<dyn Error>> {
+        let nodes = (0..network.nodes_len())
+            .map(NodeId::new)
+            .collect::<Vec<_>>();
+        let edges = (0..network.edge_len()).map(EdgeId::new).collect::<Vec<_>>();
+
+        let mut texts = TextTable::default();
+        let metadata = edges
+            .iter()
+            .map(|edge| match network.edge_metadata(*edge) {
+                Some(metadata) => MetadataRow {
+                    texts: [
+                        metadata.street_name,
+                        metadata.road_number,
+                        metadata.municipality,
+                        metadata.road_type,
+                    ]
+                    .map(|text| texts.number(text)),
+                },
+                None => MetadataRow {
+                    texts: [NO_METADATA; 4],
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let header = MapHeader {
+            magic: MAGIC,
+            version: VERSION,
+            endianness: ENDIANNESS,
+            nodes: nodes.len() as u64,
+            edges: edges.len() as u64,
+            texts: texts.numbers.len() as u64,
+            text_bytes: texts.text.len() as u64,
+        };
+
+        let junction_id = nodes
+            .iter()
+            .map(|node| network.junction_id(*node))
+            .collect::<Vec<_>>();
+        let location = nodes
+            .iter()
+            .map(|node| network.node_location(*node))
+            .collect::<Vec<_>>();
+        let (outgoing_offsets, outgoing_edges) =
+            compressed_rows(&nodes, |node| network.outgoing_edges(node));
+        let (incoming_offsets, incoming_edges) =
+            compressed_rows(&nodes, |node| network.incoming_edges(node));
+        let source = edges
+            .iter()
+            .map(|edge| network.edge_source(*edge))
+            .collect::<Vec<_>>();
+        let target = edges
+            .iter()
+            .map(|edge| network.edge_target(*edge))
+            .collect::<Vec<_>>();
+        let object_id = edges
+            .iter()
+            .map(|edge| network.edge_object_id(*edge))
+            .collect::<Vec<_>>();
+        let distance = edges
+            .iter()
+            .map(|edge| network.edge_decimetres(*edge))
+            .collect::<Vec<_>>();
+        let speed = edges
+            .iter()
+            .map(|edge| network.edge_speed(*edge))
+            .collect::<Vec<_>>();
+        let lanes = edges
+            .iter()
+            .map(|edge| network.edge_lanes(*edge))
+            .collect::<Vec<_>>();
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(bytemuck::bytes_of(&header))?;
+        writer.write_all(bytemuck::cast_slice(&junction_id))?;
+        writer.write_all(bytemuck::cast_slice(&location))?;
+        writer.write_all(bytemuck::cast_slice(&outgoing_offsets))?;
+        writer.write_all(bytemuck::cast_slice(&outgoing_edges))?;
+        writer.write_all(bytemuck::cast_slice(&incoming_offsets))?;
+        writer.write_all(bytemuck::cast_slice(&incoming_edges))?;
+        writer.write_all(bytemuck::cast_slice(&source))?;
+        writer.write_all(bytemuck::cast_slice(&target))?;
+        writer.write_all(bytemuck::cast_slice(&object_id))?;
+        writer.write_all(bytemuck::cast_slice(&distance))?;
+        writer.write_all(bytemuck::cast_slice(&metadata))?;
+        writer.write_all(bytemuck::cast_slice(&texts.offsets))?;
+        writer.write_all(&speed)?;
+        writer.write_all(&lanes)?;
+        writer.write_all(texts.text.as_bytes())?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn array<T: Pod>(&self, range: &Range<usize>) -> &[T] {
+        bytemuck::cast_slice(&self.map[range.clone()])
+    }
+
+    fn edges(&self, offsets: &Range<usize>, edges: &Range<usize>, id: NodeId) -> &[EdgeId] {
+        let offsets = self.array::<u32>(offsets);
+        &self.array(edges)[offsets[id.index()] as usize..offsets[id.index() + 1] as usize]
+    }
+
+    /// Text `number`, `None` when the file does not have it.
+    fn text(&self, number: u32) -> Option<&str> {
+        let offsets = self.array::<u32>(&self.layout.text_offsets);
+        let start = *offsets.get(number as usize)? as usize;
+        let end = *offsets.get(number as usize + 1)? as usize;
+        let bytes = self.map[self.layout.text.clone()].get(start..end)?;
+        std::str::from_utf8(bytes).ok()
+    }
+}
+
+/// The distinct texts of the metadata as they are written.
+#[derive(Debug)]
+struct TextTable {
+    numbers: HashMap<String, u32>,
+    offsets: Vec<u32>,
+    text: String,
+}
+
+impl Default for TextTable {
+    fn default() -> Self {
+        Self {
+            numbers: HashMap::new(),
+            offsets: vec![0],
+            text: String::new(),
+        }
+    }
+}
+
+impl TextTable {
+    fn number(&mut self, text: &str) -> u32 {
+        if let Some(number) = self.numbers.get(text) {
+            return *number;
+        }
+
+        let number = self.numbers.len() as u32;
+        self.text.push_str(text);
+        self.offsets.push(self.text.len() as u32);
+        self.numbers.insert(text.to_string(), number);
+        number
+    }
+}
+
+/// Checks that `offsets` start at 0, never decrease and end at `len`, so that every row is within
+/// the array of `len` elements.
+fn check_offsets(offsets: &[u32], len: usize, rows: &str) -> Result<(), String> {
+    if offsets.first() != Some(&0) || offsets.last().map(|end| *end as usize) != Some(len) {
+        return Err(format!(
+            "has offsets of {} that do not end at {}",
+            rows, len
+        ));
+    }
+    if offsets.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err(format!("has decreasing offsets of {}", rows));
+    }
+    Ok(())
+}
+
+/// Checks that every id is less than `len`.
+fn check_ids(ids: &[u32], len: usize, name: &str) -> Result<(), String> {
+    match ids.iter().find(|id| **id as usize >= len) {
+        Some(id) => Err(format!("has {} {}, but only {}", name, id, len)),
+        None => Ok(()),
+    }
+}
+
+fn compressed_rows<'a, F>(nodes: &[NodeId], edges: F) -> (Vec<u32>, Vec<EdgeId>)
+where
+    F: Fn(NodeId) -> &'a [EdgeId],
+{
+    let mut offsets = vec![0];
+    let mut rows = Vec::new();
+    for node in nodes {
+        rows.extend_from_slice(edges(*node));
+        offsets.push(EdgeId::new(rows.len()).0);
+    }
+    (offsets, rows)
+}
+
+impl Network for MappedNetwork {
+    fn nodes_len(&self) -> usize {
+        self.layout.junction_id.len() / size_of::<u32>()
+    }
+
+    fn junction_id(&self, id: NodeId) -> u32 {
+        self.array(&self.layout.junction_id)[id.index()]
+    }
+
+    fn outgoing_edges(&self, id: NodeId) -> &[EdgeId] {
+        self.edges(
+            &self.layout.outgoing_offsets,
+            &self.layout.outgoing_edges,
+            id,
+        )
+    }
+
+    fn incoming_edges(&self, id: NodeId) -> &[EdgeId] {
+        self.edges(
+            &self.layout.incoming_offsets,
+            &self.layout.incoming_edges,
+            id,
+        )
+    }
+
+    fn node_location(&self, id: NodeId) -> NodeCoord {
+        self.array(&self.layout.location)[id.index()]
+    }
+
+    fn edge_len(&self) -> usize {
+        self.layout.source.len() / size_of::<NodeId>()
+    }
+
+    fn edge_source(&self, id: EdgeId) -> NodeId {
+        self.array(&self.layout.source)[id.index()]
+    }
+
+    fn edge_target(&self, id: EdgeId) -> NodeId {
+        self.array(&self.layout.target)[id.index()]
+    }
+
+    fn edge_object_id(&self, id: EdgeId) -> u32 {
+        self.array(&self.layout.object_id)[id.index()]
+    }
+
+    fn edge_distance(&self, id: EdgeId) -> f32 {
+        self.edge_decimetres(id) as f32 / DECIMETRES_PER_METRE
+    }
+
+    fn edge_decimetres(&self, id: EdgeId) -> u32 {
+        self.array(&self.layout.distance)[id.index()]
+    }
+
+    fn edge_speed(&self, id: EdgeId) -> u8 {
+        self.array(&self.layout.speed)[id.index()]
+    }
+
+    fn edge_lanes(&self, id: EdgeId) -> u8 {
+        self.array(&self.layout.lanes)[id.index()]
+    }
+
+    /// `None` for edges without metadata, and when the texts of the edge are not in the file.
+    fn edge_metadata(&self, id: EdgeId) -> Option<EdgeMetadata<'_>> {
+        let row = self.array::<MetadataRow>(&self.layout.metadata)[id.index()];
+        if row.texts[0] == NO_METADATA {
+            return None;
+        }
+
+        let [street_name, road_number, municipality, road_type] = row.texts;
+        Some(EdgeMetadata {
+            street_name: self.text(street_name)?,
+            road_number: self.text(road_number)?,
+            municipality: self.text(municipality)?,
+            road_type: self.text(road_type)?,
+        })
+    }
+}
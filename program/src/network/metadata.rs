@@ -0,0 +1,84 @@
+// Fields of the NWB record of every road that describe it but do not change routes, such as its
+// street name. Most texts repeat, such as municipalities and road types, so every distinct text is
+// stored once and roads refer to it by its number. The wegvak id (WVK_ID) of a road is not stored
+// here, it is the object id of its edges.
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, convert::TryFrom};
+
+/// The fields of the record of a road, its wegvak id is `Network::edge_object_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EdgeMetadata<'a> {
+    /// Street name (STT_NAAM).
+    pub street_name: &'a str,
+    /// Road number (WEGNUMMER), empty for roads without one.
+    pub road_number: &'a str,
+    /// Municipality (GME_NAAM).
+    pub municipality: &'a str,
+    /// Road type (BST_CODE), empty for normal roads.
+    pub road_type: &'a str,
+}
+
+/// The metadata of roads, edges refer to it by the row `add` returns.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetadataTable {
+    rows: Vec<Row>,
+    texts: Vec<String>,
+    // Number of every text, only used to add rows and made again when it is missing.
+    #[serde(skip)]
+    numbers: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Row {
+    // Numbers of the street name, road number, municipality and road type.
+    texts: [u32; 4],
+}
+
+impl MetadataTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a row and returns its number.
+    pub fn add(&mut self, metadata: &EdgeMetadata) -> u32 {
+        let texts = [
+            metadata.street_name,
+            metadata.road_number,
+            metadata.municipality,
+            metadata.road_type,
+        ]
+        .map(|text| self.text_number(text));
+
+        self.rows.push(Row { texts });
+        u32::try_from(self.rows.len() - 1).expect("metadata row does not fit in 32 bits")
+    }
+
+    pub fn get(&self, row: u32) -> EdgeMetadata<'_> {
+        let row = &self.rows[row as usize];
+        let [street_name, road_number, municipality, road_type] =
+            row.texts.map(|number| self.texts[number as usize].as_str());
+        EdgeMetadata {
+            street_name,
+            road_number,
+            municipality,
+            road_type,
+        }
+    }
+
+    fn text_number(&mut self, text: &str) -> u32 {
+        if self.numbers.len() != self.texts.len() {
+            self.numbers = (0..)
+                .zip(&self.texts)
+                .map(|(number, text)| (text.clone(), number))
+                .collect();
+        }
+        if let Some(number) = self.numbers.get(text) {
+            return *number;
+        }
+
+        let number = u32::try_from(self.texts.len()).expect("text does not fit in 32 bits");
+        self.texts.push(text.to_string());
+        self.numbers.insert(text.to_string(), number);
+        number
+    }
+}
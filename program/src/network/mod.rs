@@ -1,33 +1,71 @@
 pub mod aos_network;
+pub mod builder;
 pub mod consts;
+pub mod file;
+pub mod generate;
+pub mod mapped;
+pub mod metadata;
 pub mod network_lite;
+pub mod nwb;
+pub mod object_index;
+pub mod reorder;
+pub mod shortcuts;
+pub mod speed;
 pub mod utils;
+pub mod weggeg;
 
-use crate::algorithm::dijkstra::DijkstraIterator;
-pub use network_lite::LiteNetwork;
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    algorithm::dijkstra::DijkstraIterator, f32_wrapper::F32Wrapper, preprocess::BuildOptions,
+};
+use bytemuck::{Pod, Zeroable};
+use file::{Layout, NetworkFile, Source, SourceCheck};
+pub use mapped::MappedNetwork;
+pub use metadata::EdgeMetadata;
+pub use network_lite::{LiteNetwork, LiteNetworkBuilder};
+pub use object_index::ObjectIndex;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use shapefile::{reader::ShapeRecordIterator, Polyline};
+pub use shortcuts::Shortcuts;
 use std::{
+    convert::TryFrom,
     error::Error,
-    fs::File,
-    io::{BufReader, Read, Write},
     ops::{Index, IndexMut},
     path::Path,
 };
 
-pub trait Network: From<ShapeRecordIterator<BufReader<File>, Polyline>> {
+pub const DECIMETRES_PER_METRE: f32 = 10.0;
+
+pub trait Network: Sized {
     fn nodes_len(&self) -> usize;
-    fn junction_id(&self, id: NodeId) -> usize;
-    fn outgoing_edges(&self, id: NodeId) -> &Vec<EdgeId>;
-    fn incoming_edges(&self, id: NodeId) -> &Vec<EdgeId>;
+    fn junction_id(&self, id: NodeId) -> u32;
+    fn outgoing_edges(&self, id: NodeId) -> &[EdgeId];
+    fn incoming_edges(&self, id: NodeId) -> &[EdgeId];
     fn node_location(&self, id: NodeId) -> NodeCoord;
 
+    fn edge_len(&self) -> usize;
     fn edge_source(&self, id: EdgeId) -> NodeId;
     fn edge_target(&self, id: EdgeId) -> NodeId;
-    fn edge_object_id(&self, id: EdgeId) -> usize;
+    fn edge_object_id(&self, id: EdgeId) -> u32;
     fn edge_distance(&self, id: EdgeId) -> f32;
+    /// Speed in km/h, 0 for edges that are not roads such as shortcuts.
+    fn edge_speed(&self, id: EdgeId) -> u8;
+    /// Number of lanes in the direction of the edge, 0 when it is not known.
+    fn edge_lanes(&self, id: EdgeId) -> u8;
+
+    /// The fields of the record of the road of the edge, `None` for edges that are not roads and
+    /// for networks that do not keep them.
+    fn edge_metadata(&self, _id: EdgeId) -> Option<EdgeMetadata<'_>> {
+        None
+    }
 
-    fn forward_dijkstra(&self, start: NodeId) -> DijkstraIterator<Self> {
+    /// The distance rounded to whole decimetres, which searches add up exactly.
+    fn edge_decimetres(&self, id: EdgeId) -> u32 {
+        (self.edge_distance(id) * DECIMETRES_PER_METRE).round() as u32
+    }
+
+    fn forward_dijkstra(&self, start: NodeId) -> DijkstraIterator<'_, Self> {
         DijkstraIterator::new(
             self,
             start,
@@ -35,13 +73,13 @@ pub trait Network: From<ShapeRecordIterator<BufReader<File>, Polyline>> {
         )
     }
 
-    fn forward_radius_neighbourhood(&self, start: NodeId, radius: usize) -> Vec<(usize, NodeId)> {
+    fn forward_radius_neighbourhood(&self, start: NodeId, radius: u64) -> Vec<(u64, NodeId)> {
         self.forward_dijkstra(start)
             .take_while(|(cost, _)| cost <= &radius)
             .collect()
     }
 
-    fn backward_dijkstra(&self, start: NodeId) -> DijkstraIterator<Self> {
+    fn backward_dijkstra(&self, start: NodeId) -> DijkstraIterator<'_, Self> {
         DijkstraIterator::new(
             self,
             start,
@@ -49,43 +87,95 @@ pub trait Network: From<ShapeRecordIterator<BufReader<File>, Polyline>> {
         )
     }
 
-    fn backward_radius_neighbourhood(&self, start: NodeId, radius: usize) -> Vec<(usize, NodeId)> {
+    fn backward_radius_neighbourhood(&self, start: NodeId, radius: u64) -> Vec<(u64, NodeId)> {
         self.backward_dijkstra(start)
             .take_while(|(cost, _)| cost <= &radius)
             .collect()
     }
 }
 
+/// The node nearest to `coord`, by straight line distance.
+pub fn closest_node<S: Network>(network: &S, coord: NodeCoord) -> NodeId {
+    (0..network.nodes_len())
+        .map(NodeId::new)
+        .min_by_key(|x| F32Wrapper(coord.distance_squared(&network.node_location(*x))))
+        .unwrap()
+}
+
+/// Networks and preprocessing that are written to and read from versioned network files, see
+/// `file::write_network` and `file::read_network`.
 pub trait Writeable: Sized {
-    fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>>;
-    fn read<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>>;
+    fn write<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &BuildOptions,
+        source: &Source,
+    ) -> Result<(), Box<dyn Error>>;
+    fn read<P: AsRef<Path>>(
+        path: P,
+        options: &BuildOptions,
+        source: SourceCheck,
+    ) -> Result<NetworkFile<Self>, Box<dyn Error>>;
 }
 
 impl<T> Writeable for T
 where
-    T: Serialize + DeserializeOwned,
+    T: Layout + Serialize + DeserializeOwned,
 {
-    fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
-        let path: &Path = path.as_ref();
-        let encoded = bincode::serialize(self)?;
-        File::create(path)?.write_all(&encoded)?;
-        Ok(())
+    fn write<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &BuildOptions,
+        source: &Source,
+    ) -> Result<(), Box<dyn Error>> {
+        file::write_network(self, path, options, source)
     }
 
-    fn read<P: AsRef<Path>>(path: P) -> Result<T, Box<dyn Error>> {
-        let path: &Path = path.as_ref();
-        let mut buffer = Vec::new();
-        File::open(path)?.read_to_end(&mut buffer)?;
-        let network: T = bincode::deserialize(&buffer)?;
-        Ok(network)
+    fn read<P: AsRef<Path>>(
+        path: P,
+        options: &BuildOptions,
+        source: SourceCheck,
+    ) -> Result<NetworkFile<T>, Box<dyn Error>> {
+        file::read_network(path, options, source)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub struct NodeId(pub usize);
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub struct EdgeId(pub usize);
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+// Ids are 32 bits to save memory on large networks. The ids and coordinates are plain old data,
+// so `MappedNetwork` can read them in place.
+#[derive(
+    Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize, Pod, Zeroable,
+)]
+#[repr(transparent)]
+pub struct NodeId(pub u32);
+#[derive(
+    Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize, Pod, Zeroable,
+)]
+#[repr(transparent)]
+pub struct EdgeId(pub u32);
+
+impl NodeId {
+    /// Panics when `index` does not fit in 32 bits.
+    pub fn new(index: usize) -> Self {
+        Self(u32::try_from(index).expect("node id does not fit in 32 bits"))
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl EdgeId {
+    /// Panics when `index` does not fit in 32 bits.
+    pub fn new(index: usize) -> Self {
+        Self(u32::try_from(index).expect("edge id does not fit in 32 bits"))
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Pod, Zeroable)]
+#[repr(C)]
 pub struct NodeCoord {
     pub x: f32,
     pub y: f32,
@@ -105,14 +195,14 @@ impl<T> Index<NodeId> for Vec<T> {
 
     #[inline]
     fn index(&self, index: NodeId) -> &Self::Output {
-        Index::index(self, index.0)
+        Index::index(self, index.index())
     }
 }
 
 impl<T> IndexMut<NodeId> for Vec<T> {
     #[inline]
     fn index_mut(&mut self, index: NodeId) -> &mut Self::Output {
-        IndexMut::index_mut(self, index.0)
+        IndexMut::index_mut(self, index.index())
     }
 }
 
@@ -121,13 +211,13 @@ impl<T> Index<EdgeId> for Vec<T> {
 
     #[inline]
     fn index(&self, index: EdgeId) -> &Self::Output {
-        Index::index(self, index.0)
+        Index::index(self, index.index())
     }
 }
 
 impl<T> IndexMut<EdgeId> for Vec<T> {
     #[inline]
     fn index_mut(&mut self, index: EdgeId) -> &mut Self::Output {
-        IndexMut::index_mut(self, index.0)
+        IndexMut::index_mut(self, index.index())
     }
 }
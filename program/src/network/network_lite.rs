@@ -1,8 +1,12 @@
-use super::{EdgeId, Network, NodeCoord, NodeId};
-use crate::network::{consts::*, utils::*};
-use serde::{Deserialize, Serialize};
-use shapefile::{reader::ShapeRecordIterator, Point, Polyline};
-use std::{collections::HashMap, fs::File, io::BufReader};
+use super::{
+    builder::{DistanceError, NetworkBuilder},
+    file::Layout,
+    metadata::MetadataTable,
+    reorder::{NodeOrder, Renumbering},
+    EdgeId, EdgeMetadata, Network, NodeCoord, NodeId, DECIMETRES_PER_METRE,
+};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{collections::HashMap, convert::TryFrom};
 // Metadata is added.
 // Information in the network:
 // Nodes:
@@ -17,21 +21,34 @@ use std::{collections::HashMap, fs::File, io::BufReader};
 // - source
 // - target
 // - distance
+// - speed
+// - lanes
 
-// Edge Metadata
-// - streetname
-// -
+// Edge Metadata, shared by the edges of a road
+// - wegvak id
+// - street name
+// - road number
+// - municipality
+// - road type
+
+/// Object id of edges that are not read from the shapefile, such as shortcuts.
+pub const SHORTCUT_OBJECT_ID: u32 = u32::MAX;
+/// Shortcuts are not roads, so they have no speed.
+pub const SHORTCUT_SPEED: u8 = 0;
+/// Metadata row of edges without metadata, such as shortcuts.
+const NO_METADATA: u32 = u32::MAX;
+
+// Distances are stored as whole decimetres, so searches add them up exactly.
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LiteNetwork {
     nodes: NodeData,
     edges: EdgeData,
+    metadata: MetadataTable,
 }
 
-impl LiteNetwork {    
-    pub fn edge_len(&self) -> usize {
-        self.edges.object_id.len()
-    }
+impl Layout for LiteNetwork {
+    const LAYOUT: &'static str = "lite network";
 }
 
 impl Network for LiteNetwork {
@@ -39,22 +56,26 @@ impl Network for LiteNetwork {
         self.nodes.junctions.len()
     }
 
-    fn junction_id(&self, id: NodeId) -> usize {
+    fn junction_id(&self, id: NodeId) -> u32 {
         self.nodes.junctions[id]
     }
 
-    fn outgoing_edges(&self, id: NodeId) -> &Vec<EdgeId> {
-        &self.nodes.outgoing_edges[id]
+    fn outgoing_edges(&self, id: NodeId) -> &[EdgeId] {
+        self.nodes.outgoing_edges.get(id)
     }
 
-    fn incoming_edges(&self, id: NodeId) -> &Vec<EdgeId> {
-        &self.nodes.incoming_edges[id]
+    fn incoming_edges(&self, id: NodeId) -> &[EdgeId] {
+        self.nodes.incoming_edges.get(id)
     }
 
     fn node_location(&self, id: NodeId) -> NodeCoord {
         self.nodes.coordinate[id]
     }
 
+    fn edge_len(&self) -> usize {
+        self.edges.object_id.len()
+    }
+
     fn edge_source(&self, id: EdgeId) -> NodeId {
         self.edges.source[id]
     }
@@ -63,29 +84,265 @@ impl Network for LiteNetwork {
         self.edges.target[id]
     }
 
-    fn edge_object_id(&self, id: EdgeId) -> usize {
+    fn edge_object_id(&self, id: EdgeId) -> u32 {
         self.edges.object_id[id]
     }
 
     fn edge_distance(&self, id: EdgeId) -> f32 {
+        self.edges.distance[id] as f32 / DECIMETRES_PER_METRE
+    }
+
+    fn edge_decimetres(&self, id: EdgeId) -> u32 {
         self.edges.distance[id]
     }
+
+    fn edge_speed(&self, id: EdgeId) -> u8 {
+        self.edges.speed[id]
+    }
+
+    fn edge_lanes(&self, id: EdgeId) -> u8 {
+        self.edges.lanes[id]
+    }
+
+    fn edge_metadata(&self, id: EdgeId) -> Option<EdgeMetadata<'_>> {
+        match self.edges.metadata[id] {
+            NO_METADATA => None,
+            row => Some(self.metadata.get(row)),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct NodeData {
-    junctions: Vec<usize>,
+    junctions: Vec<u32>,
     coordinate: Vec<NodeCoord>,
-    outgoing_edges: Vec<Vec<EdgeId>>,
-    incoming_edges: Vec<Vec<EdgeId>>,
+    outgoing_edges: Adjacency,
+    incoming_edges: Adjacency,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct EdgeData {
-    object_id: Vec<usize>,
+    object_id: Vec<u32>,
     source: Vec<NodeId>,
     target: Vec<NodeId>,
-    distance: Vec<f32>,
+    distance: Vec<u32>,
+    speed: Vec<u8>,
+    lanes: Vec<u8>,
+    // Row in the metadata table.
+    metadata: Vec<u32>,
+}
+
+/// Compressed sparse rows of the edges of every node.
+///
+/// Once compact, `start` holds a row end for every node after a 0: the edges of `node` are
+/// `edges[start[node]..start[node + 1]]`, and `len` and `capacity` are empty. Only this form is
+/// serialized.
+///
+/// Adding or removing edges first turns the rows into growing rows, where the edges of `node` are
+/// `edges[start[node]..start[node] + len[node]]` with room for `capacity[node]` edges. A full row
+/// that is not at the end of `edges` is moved there with twice the room, so edges can be added in
+/// any order. `compact` removes the room and the rows that were moved away.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(try_from = "CompactRows")]
+pub(crate) struct Adjacency {
+    pub(super) start: Vec<u32>,
+    pub(super) len: Vec<u32>,
+    pub(super) capacity: Vec<u32>,
+    pub(super) edges: Vec<EdgeId>,
+}
+
+impl Default for Adjacency {
+    fn default() -> Self {
+        Self {
+            start: vec![0],
+            len: Vec::new(),
+            capacity: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+}
+
+impl Adjacency {
+    /// Growing rows have a start and a length for every node, compact rows only the offsets.
+    pub(super) fn is_compact(&self) -> bool {
+        self.start.len() != self.len.len()
+    }
+
+    /// The number of rows.
+    pub(crate) fn nodes_len(&self) -> usize {
+        if self.is_compact() {
+            self.start.len() - 1
+        } else {
+            self.len.len()
+        }
+    }
+
+    pub(crate) fn get(&self, node: NodeId) -> &[EdgeId] {
+        let start = self.start[node] as usize;
+        let end = if self.is_compact() {
+            self.start[node.index() + 1] as usize
+        } else {
+            start + self.len[node] as usize
+        };
+        &self.edges[start..end]
+    }
+
+    pub(crate) fn add_node(&mut self) {
+        // An empty row at the end keeps compact rows compact.
+        let compact = self.is_compact();
+        self.start.push(self.end());
+        if !compact {
+            self.len.push(0);
+            self.capacity.push(0);
+        }
+    }
+
+    pub(crate) fn push(&mut self, node: NodeId, edge: EdgeId) {
+        self.grow();
+        let (start, len) = (self.start[node], self.len[node]);
+
+        if len == self.capacity[node] {
+            if len == 0 {
+                self.start[node] = self.end();
+            }
+            if self.start[node] + len == self.end() {
+                self.edges.push(edge);
+                self.len[node] += 1;
+                self.capacity[node] += 1;
+                return;
+            }
+
+            let capacity = len * 2;
+            self.start[node] = self.end();
+            self.capacity[node] = capacity;
+            self.edges
+                .extend_from_within(start as usize..(start + len) as usize);
+            self.edges
+                .resize((self.start[node] + capacity) as usize, EdgeId(u32::MAX));
+        }
+
+        self.edges[(self.start[node] + len) as usize] = edge;
+        self.len[node] += 1;
+    }
+
+    /// Keeps the edges of `node` for which `keep` holds, in the same order.
+    pub(super) fn retain<F: FnMut(&EdgeId) -> bool>(&mut self, node: NodeId, mut keep: F) {
+        self.grow();
+        let start = self.start[node] as usize;
+        let mut kept = 0;
+        for index in start..start + self.len[node] as usize {
+            let edge = self.edges[index];
+            if keep(&edge) {
+                self.edges[start + kept] = edge;
+                kept += 1;
+            }
+        }
+        self.len[node] = kept as u32;
+    }
+
+    /// Removes and returns all edges of `node`.
+    pub(super) fn take(&mut self, node: NodeId) -> Vec<EdgeId> {
+        self.grow();
+        let edges = self.get(node).to_vec();
+        self.len[node] = 0;
+        edges
+    }
+
+    pub(crate) fn compact(&mut self) {
+        if self.is_compact() {
+            return;
+        }
+
+        let mut start = Vec::with_capacity(self.len.len() + 1);
+        let mut edges = Vec::with_capacity(self.len.iter().map(|len| *len as usize).sum());
+        start.push(0);
+        for node in (0..self.len.len()).map(NodeId::new) {
+            edges.extend_from_slice(self.get(node));
+            start.push(EdgeId::new(edges.len()).0);
+        }
+        *self = Self {
+            start,
+            len: Vec::new(),
+            capacity: Vec::new(),
+            edges,
+        };
+    }
+
+    /// Turns compact rows into growing rows without room.
+    fn grow(&mut self) {
+        if !self.is_compact() {
+            return;
+        }
+
+        self.len = self
+            .start
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect();
+        self.capacity = self.len.clone();
+        self.start.pop();
+    }
+
+    /// Position after the last edge, there are at most as many as there are edge ids.
+    fn end(&self) -> u32 {
+        EdgeId::new(self.edges.len()).0
+    }
+}
+
+impl Serialize for Adjacency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_compact() {
+            CompactRowsRef {
+                start: &self.start,
+                edges: &self.edges,
+            }
+            .serialize(serializer)
+        } else {
+            let mut compact = self.clone();
+            compact.compact();
+            compact.serialize(serializer)
+        }
+    }
+}
+
+/// The serialized form of `Adjacency`.
+#[derive(Serialize)]
+struct CompactRowsRef<'a> {
+    start: &'a [u32],
+    edges: &'a [EdgeId],
+}
+
+#[derive(Deserialize)]
+struct CompactRows {
+    start: Vec<u32>,
+    edges: Vec<EdgeId>,
+}
+
+impl TryFrom<CompactRows> for Adjacency {
+    type Error = String;
+
+    fn try_from(rows: CompactRows) -> Result<Self, String> {
+        let ordered = rows.start.windows(2).all(|pair| pair[0] <= pair[1]);
+        if rows.start.first() != Some(&0)
+            || !ordered
+            || rows.start.last().map(|end| *end as usize) != Some(rows.edges.len())
+        {
+            return Err("the rows of the adjacency are not compact".to_string());
+        }
+
+        Ok(Self {
+            start: rows.start,
+            len: Vec::new(),
+            capacity: Vec::new(),
+            edges: rows.edges,
+        })
+    }
+}
+
+impl Default for LiteNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LiteNetwork {
@@ -93,8 +350,8 @@ impl LiteNetwork {
         Self {
             nodes: NodeData {
                 coordinate: Vec::new(),
-                incoming_edges: Vec::new(),
-                outgoing_edges: Vec::new(),
+                incoming_edges: Adjacency::default(),
+                outgoing_edges: Adjacency::default(),
                 junctions: Vec::new(),
             },
             edges: EdgeData {
@@ -102,123 +359,250 @@ impl LiteNetwork {
                 source: Vec::new(),
                 target: Vec::new(),
                 distance: Vec::new(),
+                speed: Vec::new(),
+                lanes: Vec::new(),
+                metadata: Vec::new(),
             },
+            metadata: MetadataTable::new(),
         }
     }
 
-    fn add_node(&mut self, junction_id: usize, coordinate: NodeCoord) -> NodeId {
-        self.nodes.outgoing_edges.push(Vec::new());
-        self.nodes.incoming_edges.push(Vec::new());
+    fn add_node(&mut self, junction_id: u32, coordinate: NodeCoord) -> NodeId {
+        let id = NodeId::new(self.nodes.junctions.len());
+        self.nodes.outgoing_edges.add_node();
+        self.nodes.incoming_edges.add_node();
         self.nodes.junctions.push(junction_id);
         self.nodes.coordinate.push(coordinate);
-        NodeId(self.nodes.junctions.len() - 1)
+        id
     }
 
     fn add_edge(
         &mut self,
-        object_id: usize,
+        object_id: u32,
         source: NodeId,
         target: NodeId,
-        distance: f32,
+        decimetres: u32,
+        speed: u8,
+        lanes: u8,
+    ) -> EdgeId {
+        let id = self.push_edge(object_id, source, target, decimetres, speed, lanes);
+        self.nodes.outgoing_edges.push(source, id);
+        self.nodes.incoming_edges.push(target, id);
+        id
+    }
+
+    /// Adds an edge without adding it to the adjacency lists.
+    fn push_edge(
+        &mut self,
+        object_id: u32,
+        source: NodeId,
+        target: NodeId,
+        decimetres: u32,
+        speed: u8,
+        lanes: u8,
     ) -> EdgeId {
+        let id = EdgeId::new(self.edges.object_id.len());
         self.edges.object_id.push(object_id);
         self.edges.source.push(source);
         self.edges.target.push(target);
-        self.edges.distance.push(distance);
-        EdgeId(self.edges.object_id.len() - 1)
+        self.edges.distance.push(decimetres);
+        self.edges.speed.push(speed);
+        self.edges.lanes.push(lanes);
+        self.edges.metadata.push(NO_METADATA);
+        id
+    }
+
+    fn add_metadata(&mut self, edges: &[EdgeId], metadata: &EdgeMetadata) {
+        let row = self.metadata.add(metadata);
+        for edge in edges {
+            self.edges.metadata[*edge] = row;
+        }
+    }
+
+    /// Adds an edge that does not belong to a road in the shapefile, `decimetres` is the sum of the
+    /// decimetres of the edges it replaces. Fails when that is more than 32 bits hold.
+    pub fn add_shortcut(
+        &mut self,
+        source: NodeId,
+        target: NodeId,
+        decimetres: u64,
+    ) -> Result<EdgeId, DistanceError> {
+        let decimetres = u32::try_from(decimetres).map_err(|_| DistanceError {
+            distance: decimetres as f32 / DECIMETRES_PER_METRE,
+        })?;
+        Ok(self.add_edge(
+            SHORTCUT_OBJECT_ID,
+            source,
+            target,
+            decimetres,
+            SHORTCUT_SPEED,
+            0,
+        ))
+    }
+
+    /// Removes all edges of a node from the adjacency lists, the edges themselves keep their id.
+    /// Returns the incoming and outgoing edges the node had.
+    pub fn disconnect_node(&mut self, node: NodeId) -> (Vec<EdgeId>, Vec<EdgeId>) {
+        let incoming = self.nodes.incoming_edges.take(node);
+        let outgoing = self.nodes.outgoing_edges.take(node);
+
+        for edge in &incoming {
+            let source = self.edges.source[*edge];
+            self.nodes.outgoing_edges.retain(source, |x| x != edge);
+        }
+        for edge in &outgoing {
+            let target = self.edges.target[*edge];
+            self.nodes.incoming_edges.retain(target, |x| x != edge);
+        }
+
+        (incoming, outgoing)
+    }
+
+    /// Removes an edge from the adjacency lists of its nodes, the edge itself keeps its id.
+    pub fn disconnect_edge(&mut self, edge: EdgeId) {
+        let source = self.edges.source[edge];
+        let target = self.edges.target[edge];
+        self.nodes.outgoing_edges.retain(source, |x| *x != edge);
+        self.nodes.incoming_edges.retain(target, |x| *x != edge);
+    }
+
+    /// Changes the distance of `edge`, it keeps its distance when `distance` can not be stored.
+    pub fn set_edge_distance(&mut self, edge: EdgeId, distance: f32) -> Result<(), DistanceError> {
+        self.edges.distance[edge] = stored_distance(distance)?;
+        Ok(())
     }
-}
 
-impl From<ShapeRecordIterator<BufReader<File>, Polyline>> for LiteNetwork {
-    fn from(shapes: ShapeRecordIterator<BufReader<File>, Polyline>) -> Self {
+    /// A copy with the nodes renumbered in `order` and the edges renumbered by their new source
+    /// node, together with the mapping from the old ids to the new ids.
+    pub fn reordered(&self, order: NodeOrder) -> (LiteNetwork, Renumbering) {
+        let node_order = order.order(self);
+        let mut edge_order = node_order
+            .iter()
+            .flat_map(|node| self.outgoing_edges(*node).iter().copied())
+            .collect::<Vec<_>>();
+        // Disconnected edges are not in any adjacency list but keep their place after the others.
+        let mut connected = vec![false; self.edge_len()];
+        for edge in &edge_order {
+            connected[*edge] = true;
+        }
+        edge_order.extend(
+            (0..self.edge_len())
+                .map(EdgeId::new)
+                .filter(|edge| !connected[*edge]),
+        );
+
+        let renumbering = Renumbering::new(&node_order, &edge_order);
+
         let mut network = LiteNetwork::new();
-        let mut mapping = HashMap::new();
-        let mut shape_counter = 0;
-
-        for index in shapes {
-            let (shape, record) = index.unwrap();
-
-            let direction: RoadDirection =
-                get_character(&record, DIRECTION).unwrap().parse().unwrap();
-            let junction_start = get_numeric(&record, NODE_START).unwrap() as usize;
-            let junction_end = get_numeric(&record, NODE_END).unwrap() as usize;
-
-            let node_start_id = handle_node(
-                &mut network,
-                &mut mapping,
-                junction_start,
-                shape.part(0).and_then(|x| x.first()).unwrap(),
-            );
-            let node_end_id = handle_node(
-                &mut network,
-                &mut mapping,
-                junction_end,
-                shape.part(0).and_then(|x| x.last()).unwrap(),
+        for node in &node_order {
+            network.add_node(self.junction_id(*node), self.node_location(*node));
+        }
+        network.metadata = self.metadata.clone();
+        for edge in &edge_order {
+            let new = network.push_edge(
+                self.edge_object_id(*edge),
+                renumbering.node(self.edge_source(*edge)),
+                renumbering.node(self.edge_target(*edge)),
+                self.edge_decimetres(*edge),
+                self.edge_speed(*edge),
+                self.edge_lanes(*edge),
             );
-
-            let shape_distance = calculate_distance(&shape);
-
-            match direction {
-                RoadDirection::BOTH => {
-                    let id0 =
-                        network.add_edge(shape_counter, node_start_id, node_end_id, shape_distance);
-                    let id1 =
-                        network.add_edge(shape_counter, node_end_id, node_start_id, shape_distance);
-
-                    network.nodes.outgoing_edges[node_start_id].push(id0);
-                    network.nodes.incoming_edges[node_end_id].push(id0);
-
-                    network.nodes.outgoing_edges[node_end_id].push(id1);
-                    network.nodes.incoming_edges[node_start_id].push(id1);
-                }
-                RoadDirection::WITH => {
-                    let id0 =
-                        network.add_edge(shape_counter, node_start_id, node_end_id, shape_distance);
-                    network.nodes.incoming_edges[node_end_id].push(id0);
-                    network.nodes.outgoing_edges[node_start_id].push(id0);
-                }
-                RoadDirection::AGAINST => {
-                    let id1 =
-                        network.add_edge(shape_counter, node_end_id, node_start_id, shape_distance);
-                    network.nodes.outgoing_edges[node_end_id].push(id1);
-                    network.nodes.incoming_edges[node_start_id].push(id1);
-                }
+            network.edges.metadata[new] = self.edges.metadata[*edge];
+        }
+        for edge in edge_order.iter().filter(|edge| connected[**edge]) {
+            let new = renumbering.edge(*edge);
+            network
+                .nodes
+                .outgoing_edges
+                .push(network.edges.source[new], new);
+        }
+        for node in &node_order {
+            for edge in self.incoming_edges(*node) {
+                let new = renumbering.edge(*edge);
+                network
+                    .nodes
+                    .incoming_edges
+                    .push(network.edges.target[new], new);
             }
-            shape_counter += 1;
         }
+        network.compact();
 
-        let nodes = &network.nodes;
-        assert_eq!(mapping.len(), nodes.junctions.len());
-        assert_eq!(nodes.junctions.len(), nodes.outgoing_edges.len());
-        assert_eq!(nodes.junctions.len(), nodes.incoming_edges.len());
-        assert_eq!(nodes.junctions.len(), nodes.coordinate.len());
+        (network, renumbering)
+    }
 
-        let edges = &network.edges;
-        assert_eq!(edges.object_id.len(), edges.source.len());
-        assert_eq!(edges.object_id.len(), edges.target.len());
-        assert_eq!(edges.object_id.len(), edges.distance.len());
+    /// Puts the edges of every node next to each other in node order, which makes searches
+    /// faster after many edges are added.
+    pub fn compact(&mut self) {
+        self.nodes.outgoing_edges.compact();
+        self.nodes.incoming_edges.compact();
+    }
 
-        network
+    /// Whether the edges of every node are next to each other, until an edge is added.
+    pub fn is_compact(&self) -> bool {
+        self.nodes.outgoing_edges.is_compact() && self.nodes.incoming_edges.is_compact()
     }
 }
 
-fn handle_node(
-    network: &mut LiteNetwork,
-    mapping: &mut HashMap<usize, NodeId>,
-    junction_id: usize,
-    point: &Point,
-) -> NodeId {
-    if let Some(id) = mapping.get(&junction_id) {
-        *id
+/// Builds a `LiteNetwork`, the adjacency lists are compacted when the network is built.
+#[derive(Debug, Default)]
+pub struct LiteNetworkBuilder {
+    network: LiteNetwork,
+    junctions: HashMap<u32, NodeId>,
+}
+
+impl LiteNetworkBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NetworkBuilder for LiteNetworkBuilder {
+    type Network = LiteNetwork;
+
+    fn add_junction(&mut self, junction_id: u32, location: NodeCoord) -> NodeId {
+        let network = &mut self.network;
+        *self
+            .junctions
+            .entry(junction_id)
+            .or_insert_with(|| network.add_node(junction_id, location))
+    }
+
+    fn add_edge(
+        &mut self,
+        object_id: u32,
+        source: NodeId,
+        target: NodeId,
+        distance: f32,
+        speed: u8,
+        lanes: u8,
+    ) -> Result<EdgeId, DistanceError> {
+        Ok(self.network.add_edge(
+            object_id,
+            source,
+            target,
+            stored_distance(distance)?,
+            speed,
+            lanes,
+        ))
+    }
+
+    fn add_metadata(&mut self, edges: &[EdgeId], metadata: &EdgeMetadata) {
+        self.network.add_metadata(edges, metadata)
+    }
+
+    fn build(mut self) -> LiteNetwork {
+        self.network.compact();
+        self.network
+    }
+}
+
+/// A distance in metres as whole decimetres, when they fit in 32 bits. The bound is compared as
+/// f64, because `u32::MAX as f32` rounds up to 2^32.
+pub(crate) fn stored_distance(distance: f32) -> Result<u32, DistanceError> {
+    let decimetres = f64::from((distance * DECIMETRES_PER_METRE).round());
+    if decimetres >= 0.0 && decimetres <= f64::from(u32::MAX) {
+        Ok(decimetres as u32)
     } else {
-        let id = network.add_node(
-            junction_id,
-            NodeCoord {
-                x: point.x as f32,
-                y: point.y as f32,
-            },
-        );
-        mapping.insert(junction_id, id);
-        id
+        Err(DistanceError { distance })
     }
 }
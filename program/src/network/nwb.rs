@@ -0,0 +1,110 @@
+// Importer of the Wegvakken shapefile of the Nationaal Wegenbestand (NWB). Every record is a road
+// between a start and an end junction, the junctions are placed at the ends of the road's shape.
+// The speed of a road follows from the fields of its record by a `SpeedTable`, and from the maximum
+// speeds of the wegkenmerken where they are known. The wegkenmerken can differ per direction, so
+// every direction of a road gets its own speed and lanes. The edges of a road share the metadata of
+// its record.
+use super::{
+    builder::NetworkBuilder, builder::Road, consts::*, speed::SpeedTable, utils::*,
+    weggeg::RoadAttributes, EdgeMetadata, NodeCoord,
+};
+use shapefile::{
+    dbase::{FieldValue, Record},
+    reader::ShapeRecordIterator,
+    Point, Polyline,
+};
+use std::{error::Error, fs::File, io::BufReader, path::Path};
+
+/// Reads the shapefile at `path` into a network.
+pub fn read<B, P>(
+    path: P,
+    speeds: &SpeedTable,
+    attributes: &RoadAttributes,
+    builder: B,
+) -> Result<B::Network, Box<dyn Error>>
+where
+    B: NetworkBuilder,
+    P: AsRef<Path>,
+{
+    import(read_shapes(path)?, speeds, attributes, builder)
+}
+
+/// Adds the roads of the shapefile records to `builder`, the object id of a road is its wegvak id
+/// (WVK_ID), which stays the same when the shapefile is exported again.
+pub fn import<B: NetworkBuilder>(
+    shapes: ShapeRecordIterator<BufReader<File>, Polyline>,
+    speeds: &SpeedTable,
+    attributes: &RoadAttributes,
+    mut builder: B,
+) -> Result<B::Network, Box<dyn Error>> {
+    for entry in shapes {
+        let (shape, record) = entry?;
+        let wegvak = wegvak_id(get_numeric(&record, WEGVAK_ID)?)?;
+        let direction: RoadDirection = get_character(&record, DIRECTION)?.parse()?;
+        let junction_start = junction_id(get_numeric(&record, NODE_START)?)?;
+        let junction_end = junction_id(get_numeric(&record, NODE_END)?)?;
+        let speed = speeds
+            .speed(&record)
+            .ok_or_else(|| format!("Road {} matches no speed rule", wegvak))?;
+
+        let points = shape
+            .part(0)
+            .filter(|points| !points.is_empty())
+            .ok_or_else(|| format!("Road {} has no points", wegvak))?;
+        let start = builder.add_junction(junction_start, location(&points[0]));
+        let end = builder.add_junction(junction_end, location(&points[points.len() - 1]));
+
+        let distance = calculate_distance(&shape);
+        let directions = match direction {
+            RoadDirection::BOTH => vec![RoadDirection::WITH, RoadDirection::AGAINST],
+            direction => vec![direction],
+        };
+        let mut edges = Vec::new();
+        for direction in directions {
+            let added = builder
+                .add_road(&Road {
+                    object_id: wegvak,
+                    start,
+                    end,
+                    distance,
+                    speed: attributes.speed(wegvak, distance, direction, speed),
+                    lanes: attributes.lanes(wegvak, distance, direction),
+                    direction,
+                })
+                .map_err(|error| format!("Road {}: {}", wegvak, error))?;
+            edges.extend(added);
+        }
+
+        let metadata = EdgeMetadata {
+            street_name: text(&record, STREET_NAME),
+            road_number: text(&record, ROAD_NUMBER),
+            municipality: text(&record, MUNICIPALITY),
+            road_type: text(&record, ROAD_TYPE),
+        };
+        builder.add_metadata(&edges, &metadata);
+    }
+
+    Ok(builder.build())
+}
+
+pub fn read_shapes<P: AsRef<Path>>(
+    path: P,
+) -> Result<ShapeRecordIterator<BufReader<File>, Polyline>, shapefile::Error> {
+    shapefile::Reader::from_path(path)
+        .and_then(|reader| reader.iter_shapes_and_records_as::<Polyline>())
+}
+
+/// The text of `field` without padding, empty when the record does not have it.
+fn text<'a>(record: &'a Record, field: &str) -> &'a str {
+    match record.get(field) {
+        Some(FieldValue::Character(Some(text))) => text.trim(),
+        _ => "",
+    }
+}
+
+fn location(point: &Point) -> NodeCoord {
+    NodeCoord {
+        x: point.x as f32,
+        y: point.y as f32,
+    }
+}
@@ -0,0 +1,33 @@
+// Finds the edges of a road by its object id, which for the NWB is the wegvak id (WVK_ID). A road
+// has an edge for every direction it can be driven in, so one or two edges. Works for any
+// `Network`, the index is made from the object ids of its edges.
+use super::{network_lite::SHORTCUT_OBJECT_ID, EdgeId, Network};
+
+#[derive(Debug, Clone, Default)]
+pub struct ObjectIndex {
+    // Sorted by object id, and by edge for the same object id.
+    object_ids: Vec<u32>,
+    edges: Vec<EdgeId>,
+}
+
+impl ObjectIndex {
+    /// Indexes all edges of `network` except shortcuts.
+    pub fn new<S: Network>(network: &S) -> Self {
+        let mut pairs = (0..network.edge_len())
+            .map(EdgeId::new)
+            .map(|edge| (network.edge_object_id(edge), edge))
+            .filter(|(object_id, _)| *object_id != SHORTCUT_OBJECT_ID)
+            .collect::<Vec<_>>();
+        pairs.sort_unstable();
+
+        let (object_ids, edges) = pairs.into_iter().unzip();
+        Self { object_ids, edges }
+    }
+
+    /// The edges of the road with `object_id`, empty when there is no such road.
+    pub fn edges(&self, object_id: u32) -> &[EdgeId] {
+        let start = self.object_ids.partition_point(|id| *id < object_id);
+        let end = self.object_ids.partition_point(|id| *id <= object_id);
+        &self.edges[start..end]
+    }
+}
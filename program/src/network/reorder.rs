@@ -0,0 +1,148 @@
+// Orders for renumbering the nodes of a network, so nodes that are close in the network are also
+// close in memory. The edges are renumbered to follow their source node, see
+// `LiteNetwork::reordered`.
+use super::{EdgeId, Network, NodeId};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeOrder {
+    /// Along a Hilbert curve through the locations of the nodes.
+    Hilbert,
+    /// Breadth first search order, ignoring the direction of edges.
+    BreadthFirst,
+}
+
+/// Maps the ids of a network to the ids of its reordered copy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Renumbering {
+    nodes: Vec<NodeId>,
+    edges: Vec<EdgeId>,
+}
+
+impl Renumbering {
+    /// The orders list the old ids in their new order.
+    pub(crate) fn new(node_order: &[NodeId], edge_order: &[EdgeId]) -> Self {
+        let mut nodes = vec![NodeId(0); node_order.len()];
+        for (new, old) in node_order.iter().enumerate() {
+            nodes[*old] = NodeId::new(new);
+        }
+        let mut edges = vec![EdgeId(0); edge_order.len()];
+        for (new, old) in edge_order.iter().enumerate() {
+            edges[*old] = EdgeId::new(new);
+        }
+
+        Self { nodes, edges }
+    }
+
+    pub fn node(&self, old: NodeId) -> NodeId {
+        self.nodes[old]
+    }
+
+    pub fn edge(&self, old: EdgeId) -> EdgeId {
+        self.edges[old]
+    }
+}
+
+impl NodeOrder {
+    /// The nodes of `network` in this order.
+    pub fn order<S: Network>(&self, network: &S) -> Vec<NodeId> {
+        match self {
+            NodeOrder::Hilbert => hilbert_order(network),
+            NodeOrder::BreadthFirst => breadth_first_order(network),
+        }
+    }
+}
+
+fn hilbert_order<S: Network>(network: &S) -> Vec<NodeId> {
+    const SIDE: u32 = 1 << 16;
+
+    let mut nodes = (0..network.nodes_len())
+        .map(NodeId::new)
+        .collect::<Vec<_>>();
+    let locations = nodes
+        .iter()
+        .map(|node| network.node_location(*node))
+        .collect::<Vec<_>>();
+
+    let (min_x, max_x) = locations
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), l| {
+            (min.min(l.x), max.max(l.x))
+        });
+    let (min_y, max_y) = locations
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), l| {
+            (min.min(l.y), max.max(l.y))
+        });
+    let scale = (SIDE - 1) as f32 / (max_x - min_x).max(max_y - min_y).max(f32::EPSILON);
+
+    nodes.sort_by_key(|node| {
+        let location = locations[*node];
+        hilbert_index(
+            SIDE,
+            ((location.x - min_x) * scale) as u32,
+            ((location.y - min_y) * scale) as u32,
+        )
+    });
+    nodes
+}
+
+/// Position of the cell (x, y) along the Hilbert curve through a grid of `side` by `side`.
+fn hilbert_index(side: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut index = 0u64;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        index += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+
+        // Rotate the quadrant so the curve continues in the same orientation.
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
+
+fn breadth_first_order<S: Network>(network: &S) -> Vec<NodeId> {
+    let mut visited = vec![false; network.nodes_len()];
+    let mut order = Vec::with_capacity(network.nodes_len());
+    let mut queue = VecDeque::new();
+
+    for start in (0..network.nodes_len()).map(NodeId::new) {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        queue.push_back(start);
+
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+
+            let neighbours = network
+                .outgoing_edges(node)
+                .iter()
+                .map(|edge| network.edge_target(*edge))
+                .chain(
+                    network
+                        .incoming_edges(node)
+                        .iter()
+                        .map(|edge| network.edge_source(*edge)),
+                );
+            for neighbour in neighbours {
+                if !visited[neighbour] {
+                    visited[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+
+    order
+}
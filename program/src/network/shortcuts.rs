@@ -0,0 +1,81 @@
+use super::EdgeId;
+use crate::algorithm::EdgePath;
+use serde::{Deserialize, Serialize};
+
+/// The paths replaced by shortcuts. Shortcuts are appended after the edges of the network they
+/// were added to, so every edge id below `first_shortcut` is an original edge.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Shortcuts {
+    first_shortcut: usize,
+    // The replaced path of shortcut i is `edges[offsets[i]..offsets[i + 1]]`.
+    offsets: Vec<usize>,
+    edges: Vec<EdgeId>,
+}
+
+impl Shortcuts {
+    pub fn new(first_shortcut: usize) -> Self {
+        Self {
+            first_shortcut,
+            offsets: vec![0],
+            edges: Vec::new(),
+        }
+    }
+
+    /// Registers `shortcut` as replacing `path`, the path itself can contain shortcuts.
+    pub fn insert(&mut self, shortcut: EdgeId, path: &[EdgeId]) {
+        assert_eq!(
+            shortcut.index(),
+            self.first_shortcut + self.len(),
+            "shortcuts are registered in order"
+        );
+        self.edges.extend_from_slice(path);
+        self.offsets.push(self.edges.len());
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_shortcut(&self, edge: EdgeId) -> bool {
+        edge.index() >= self.first_shortcut
+    }
+
+    /// The path a shortcut replaces, `None` for an original edge.
+    pub fn replaces(&self, edge: EdgeId) -> Option<&[EdgeId]> {
+        edge.index()
+            .checked_sub(self.first_shortcut)
+            .map(|index| &self.edges[self.offsets[index]..self.offsets[index + 1]])
+    }
+
+    /// Appends the original edges behind `edge` to `into`.
+    pub fn unpack_edge(&self, edge: EdgeId, into: &mut Vec<EdgeId>) {
+        let mut stack = vec![edge];
+        while let Some(edge) = stack.pop() {
+            match self.replaces(edge) {
+                Some(path) => stack.extend(path.iter().rev()),
+                None => into.push(edge),
+            }
+        }
+    }
+
+    pub fn unpack_edges(&self, edges: &[EdgeId]) -> Vec<EdgeId> {
+        let mut unpacked = Vec::with_capacity(edges.len());
+        for edge in edges {
+            self.unpack_edge(*edge, &mut unpacked);
+        }
+        unpacked
+    }
+
+    /// The same path with every shortcut replaced by the original edges.
+    pub fn unpack_path(&self, path: &EdgePath) -> EdgePath {
+        EdgePath {
+            source: path.source,
+            target: path.target,
+            edges: self.unpack_edges(&path.edges),
+        }
+    }
+}
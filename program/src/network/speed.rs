@@ -0,0 +1,117 @@
+// Speeds of roads, derived from the fields of their Wegvakken records when the network is built.
+//
+// A speed table is a list of rules, one per line: conditions `FIELD=VALUE` followed by a speed in
+// km/h. The first rule whose conditions all hold for a record gives the speed of its road. The
+// value `*` matches any value that is not empty, and a rule without conditions matches every road.
+// Text after `#` is a comment.
+use serde::{Deserialize, Serialize};
+use shapefile::dbase::FieldValue;
+use std::{collections::HashMap, error::Error, fs, path::Path, str::FromStr};
+
+/// The table used when none is given: by road type (BST_CODE), then numbered roads of the state
+/// (WEGBEHSRT and WEGNUMMER), then by the kind of road authority (WEGBEHSRT).
+pub const DEFAULT_SPEEDS: &str = "\
+# Roundabouts, parking places, service roads and cycle paths.
+BST_CODE=RB 30
+BST_CODE=PKP 30
+BST_CODE=VWG 30
+BST_CODE=FP 15
+# Ramps and connections between motorways.
+BST_CODE=OPR 70
+BST_CODE=AFR 70
+BST_CODE=VBD 70
+# State roads with a number are motorways.
+WEGBEHSRT=R WEGNUMMER=* 100
+WEGBEHSRT=R 80
+WEGBEHSRT=P 80
+WEGBEHSRT=W 60
+50
+";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedTable {
+    rules: Vec<SpeedRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SpeedRule {
+    // Field and value, `None` for any value that is not empty.
+    conditions: Vec<(String, Option<String>)>,
+    speed: u8,
+}
+
+impl SpeedTable {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        text.parse()
+            .map_err(|error| format!("{:?}: {}", path, error).into())
+    }
+
+    /// Speed in km/h of the road of `record`, `None` when no rule matches.
+    pub fn speed(&self, record: &HashMap<String, FieldValue>) -> Option<u8> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.conditions
+                    .iter()
+                    .all(|(field, value)| matches(record, field, value.as_deref()))
+            })
+            .map(|rule| rule.speed)
+    }
+}
+
+impl Default for SpeedTable {
+    fn default() -> Self {
+        DEFAULT_SPEEDS
+            .parse()
+            .expect("default speed table is valid")
+    }
+}
+
+impl FromStr for SpeedTable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Vec::new();
+        for (number, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace().collect::<Vec<_>>();
+            let speed = match words.pop() {
+                Some(speed) => speed,
+                None => continue,
+            };
+            let speed = match speed.parse::<u8>() {
+                Ok(speed) if speed > 0 => speed,
+                _ => return Err(format!("line {}: invalid speed {}", number + 1, speed)),
+            };
+
+            let conditions = words
+                .iter()
+                .map(|condition| match condition.split_once('=') {
+                    Some((field, "*")) => Ok((field.to_string(), None)),
+                    Some((field, value)) => Ok((field.to_string(), Some(value.to_string()))),
+                    None => Err(format!(
+                        "line {}: {} is not FIELD=VALUE",
+                        number + 1,
+                        condition
+                    )),
+                })
+                .collect::<Result<_, _>>()?;
+            rules.push(SpeedRule { conditions, speed });
+        }
+        Ok(Self { rules })
+    }
+}
+
+/// Whether the text field `field` of `record` has `value`, or any value when it is `None`.
+fn matches(record: &HashMap<String, FieldValue>, field: &str, value: Option<&str>) -> bool {
+    let found = match record.get(field) {
+        Some(FieldValue::Character(Some(found))) => found.trim(),
+        _ => return false,
+    };
+    match value {
+        Some(value) => found == value,
+        None => !found.is_empty(),
+    }
+}
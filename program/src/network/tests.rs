@@ -0,0 +1,863 @@
+// The same checks for every `Network` backend, on a small network with one-way roads and a node
+// that can be reached but not left.
+use super::{
+    aos_network::{AoSNetwork, AoSNetworkBuilder},
+    builder::{NetworkBuilder, Road},
+    closest_node,
+    file::{self, FileStamp, Layout, NetworkFile, Source, SourceCheck, FORMAT_VERSION},
+    generate,
+    network_lite::Adjacency,
+    reorder::NodeOrder,
+    speed::SpeedTable,
+    utils::RoadDirection,
+    weggeg::{Part, RoadAttributes},
+    EdgeId, EdgeMetadata, LiteNetwork, LiteNetworkBuilder, MappedNetwork, Network, NodeCoord,
+    NodeId, ObjectIndex, Writeable,
+};
+use crate::{
+    algorithm::{
+        bidirectional::bidirectional_path,
+        dijkstra::{DijkstraDirection, DijkstraIterator},
+    },
+    highway::HighwayHierarchy,
+    preprocess::{self, BuildOptions},
+};
+use serde::de::DeserializeOwned;
+use shapefile::dbase::FieldValue;
+use std::{
+    collections::HashMap,
+    fs,
+    mem::size_of,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+// Junction id and location, the nodes get these ids in order.
+const JUNCTIONS: [(u32, f32, f32); 7] = [
+    (600_000_101, 0.0, 0.0),
+    (600_000_102, 100.0, 0.0),
+    (600_000_103, 200.0, 0.0),
+    (600_000_104, 0.0, 100.0),
+    (600_000_105, 100.0, 100.0),
+    (600_000_106, 200.0, 100.0),
+    (600_000_107, 300.0, 50.0),
+];
+
+// Object id, start and end node, distance, speed, lanes and direction as in the shapefile.
+const ROADS: [(u32, usize, usize, f32, u8, u8, &str); 9] = [
+    (0, 0, 1, 100.0, 50, 1, "B"),
+    (1, 1, 2, 120.0, 50, 1, "B"),
+    (2, 0, 3, 100.0, 30, 1, "B"),
+    (3, 3, 4, 100.0, 30, 1, "H"),
+    (4, 4, 1, 100.0, 30, 1, "T"),
+    (5, 4, 5, 110.0, 80, 2, "B"),
+    (6, 2, 5, 100.0, 50, 1, "B"),
+    (7, 5, 6, 150.0, 100, 3, "H"),
+    (8, 1, 5, 160.0, 80, 2, "T"),
+];
+
+/// The edges of the roads as (object id, source, target, distance, speed, lanes).
+fn directed_edges() -> Vec<(u32, usize, usize, f32, u8, u8)> {
+    let mut edges = Vec::new();
+    for (object_id, start, end, distance, speed, lanes, direction) in ROADS.iter() {
+        let edge = |source: &usize, target: &usize| {
+            (*object_id, *source, *target, *distance, *speed, *lanes)
+        };
+        match direction.parse().unwrap() {
+            RoadDirection::BOTH => {
+                edges.push(edge(start, end));
+                edges.push(edge(end, start));
+            }
+            RoadDirection::WITH => edges.push(edge(start, end)),
+            RoadDirection::AGAINST => edges.push(edge(end, start)),
+        }
+    }
+    edges
+}
+
+fn location(x: f32, y: f32) -> NodeCoord {
+    NodeCoord { x, y }
+}
+
+/// Metadata of a road, some roads have the same texts.
+fn metadata(object_id: u32) -> EdgeMetadata<'static> {
+    EdgeMetadata {
+        street_name: ["Dorpsstraat", "Kerkweg", ""][object_id as usize % 3],
+        road_number: if object_id == 7 { "004" } else { "" },
+        municipality: "Utrecht",
+        road_type: if object_id == 7 { "HR" } else { "" },
+    }
+}
+
+fn build<B: NetworkBuilder>(mut builder: B) -> B::Network {
+    let nodes = JUNCTIONS
+        .iter()
+        .map(|(junction_id, x, y)| builder.add_junction(*junction_id, location(*x, *y)))
+        .collect::<Vec<_>>();
+    // Adding a junction again returns the node it already has.
+    let (junction_id, x, y) = JUNCTIONS[0];
+    assert_eq!(builder.add_junction(junction_id, location(x, y)), nodes[0]);
+
+    for (object_id, start, end, distance, speed, lanes, direction) in ROADS.iter() {
+        let edges = builder
+            .add_road(&Road {
+                object_id: *object_id,
+                start: nodes[*start],
+                end: nodes[*end],
+                distance: *distance,
+                speed: *speed,
+                lanes: *lanes,
+                direction: direction.parse().unwrap(),
+            })
+            .unwrap();
+        builder.add_metadata(&edges, &metadata(*object_id));
+    }
+    builder.build()
+}
+
+/// Shortest distances in decimetres between all nodes, `None` when there is no path.
+fn shortest_distances() -> Vec<Vec<Option<u64>>> {
+    let n = JUNCTIONS.len();
+    let mut distances = vec![vec![None; n]; n];
+    for (node, row) in distances.iter_mut().enumerate() {
+        row[node] = Some(0);
+    }
+    for (_, source, target, distance, _, _) in directed_edges() {
+        distances[source][target] = shorter(distances[source][target], distance as u64 * 10);
+    }
+
+    for via in 0..n {
+        for source in 0..n {
+            for target in 0..n {
+                if let (Some(a), Some(b)) = (distances[source][via], distances[via][target]) {
+                    distances[source][target] = shorter(distances[source][target], a + b);
+                }
+            }
+        }
+    }
+    distances
+}
+
+fn shorter(distance: Option<u64>, other: u64) -> Option<u64> {
+    Some(distance.map_or(other, |distance| distance.min(other)))
+}
+
+fn check_network<S: Network>(network: &S) {
+    assert_eq!(network.nodes_len(), JUNCTIONS.len());
+    for (node, (junction_id, x, y)) in JUNCTIONS.iter().enumerate() {
+        let node = NodeId::new(node);
+        assert_eq!(network.junction_id(node), *junction_id);
+        assert_eq!(network.node_location(node), location(*x, *y));
+    }
+
+    let mut expected = directed_edges();
+    let mut edges = (0..network.edge_len())
+        .map(EdgeId::new)
+        .map(|edge| {
+            (
+                network.edge_object_id(edge),
+                network.edge_source(edge).index(),
+                network.edge_target(edge).index(),
+                network.edge_distance(edge),
+                network.edge_speed(edge),
+                network.edge_lanes(edge),
+            )
+        })
+        .collect::<Vec<_>>();
+    expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    edges.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(edges, expected);
+
+    for edge in (0..network.edge_len()).map(EdgeId::new) {
+        let expected = metadata(network.edge_object_id(edge));
+        assert_eq!(network.edge_metadata(edge), Some(expected));
+    }
+
+    let (mut outgoing, mut incoming) = (0, 0);
+    for node in (0..network.nodes_len()).map(NodeId::new) {
+        for edge in network.outgoing_edges(node) {
+            assert_eq!(network.edge_source(*edge), node);
+            outgoing += 1;
+        }
+        for edge in network.incoming_edges(node) {
+            assert_eq!(network.edge_target(*edge), node);
+            incoming += 1;
+        }
+    }
+    assert_eq!(outgoing, network.edge_len());
+    assert_eq!(incoming, network.edge_len());
+}
+
+fn check_dijkstra<S: Network>(network: &S) {
+    let distances = shortest_distances();
+
+    for start in 0..network.nodes_len() {
+        let forward =
+            DijkstraIterator::new(network, NodeId::new(start), DijkstraDirection::Forward)
+                .map(|(cost, node)| (node.index(), cost))
+                .collect::<Vec<_>>();
+        for (node, cost) in &forward {
+            assert_eq!(distances[start][*node], Some(*cost));
+        }
+        let reachable = distances[start].iter().filter(|d| d.is_some()).count();
+        assert_eq!(forward.len(), reachable);
+
+        let backward =
+            DijkstraIterator::new(network, NodeId::new(start), DijkstraDirection::Backward)
+                .map(|(cost, node)| (node.index(), cost))
+                .collect::<Vec<_>>();
+        for (node, cost) in &backward {
+            assert_eq!(distances[*node][start], Some(*cost));
+        }
+        let reaching = distances.iter().filter(|row| row[start].is_some()).count();
+        assert_eq!(backward.len(), reaching);
+    }
+}
+
+fn check_bidirectional<S: Network>(network: &S) {
+    let distances = shortest_distances();
+
+    for source in (0..network.nodes_len()).map(NodeId::new) {
+        for target in (0..network.nodes_len()).map(NodeId::new) {
+            let path = bidirectional_path(
+                network,
+                |node, direction| direction.neighbours(node, network),
+                source,
+                target,
+            );
+
+            match distances[source.index()][target.index()] {
+                Some(distance) => {
+                    let (cost, edges) = path.unwrap();
+                    assert_eq!(cost, distance);
+
+                    let mut node = source;
+                    for edge in &edges {
+                        assert_eq!(network.edge_source(*edge), node);
+                        node = network.edge_target(*edge);
+                    }
+                    assert_eq!(node, target);
+                    let length: f32 = edges.iter().map(|e| network.edge_distance(*e)).sum();
+                    assert_eq!(length as u64 * 10, distance);
+                }
+                None => assert!(path.is_err()),
+            }
+        }
+    }
+}
+
+fn check_all<S: Network>(network: &S) {
+    check_network(network);
+    check_dijkstra(network);
+    check_bidirectional(network);
+}
+
+#[test]
+fn lite_network_passes() {
+    check_all(&build(LiteNetworkBuilder::new()));
+}
+
+#[test]
+fn aos_network_passes() {
+    check_all(&build(AoSNetworkBuilder::new()));
+}
+
+#[test]
+fn aos_network_copied_from_lite_network() {
+    let lite = build(LiteNetworkBuilder::new());
+    let aos = AoSNetwork::from_network(&lite);
+    check_all(&aos);
+
+    for edge in (0..lite.edge_len()).map(EdgeId::new) {
+        assert_eq!(aos.edge_source(edge), lite.edge_source(edge));
+        assert_eq!(aos.edge_target(edge), lite.edge_target(edge));
+    }
+    for node in (0..lite.nodes_len()).map(NodeId::new) {
+        assert_eq!(aos.outgoing_edges(node), lite.outgoing_edges(node));
+        assert_eq!(aos.incoming_edges(node), lite.incoming_edges(node));
+    }
+}
+
+/// Adds edges of distances that can not be stored, which must be rejected, and one of a distance
+/// that just can.
+fn build_long_edge<B: NetworkBuilder>(mut builder: B) -> (B::Network, EdgeId) {
+    let [start, end] = [0, 1].map(|junction| builder.add_junction(junction, location(0.0, 0.0)));
+    // The last rounds to 2^32 decimetres, just more than fits in 32 bits.
+    for distance in [-1.0, f32::NAN, 429_496_736.0] {
+        let added = builder.add_edge(0, start, end, distance, 50, 1);
+        assert!(added.is_err(), "{} is stored", distance);
+    }
+
+    let edge = builder
+        .add_edge(0, start, end, 400_000_000.0, 50, 1)
+        .unwrap();
+    let network = builder.build();
+    assert_eq!(network.edge_len(), 1);
+    assert_eq!(network.edge_decimetres(edge), 4_000_000_000);
+    (network, edge)
+}
+
+#[test]
+fn lite_network_rejects_distances_it_can_not_store() {
+    let (mut network, edge) = build_long_edge(LiteNetworkBuilder::new());
+    assert!(network.set_edge_distance(edge, -1.0).is_err());
+    assert_eq!(network.edge_decimetres(edge), 4_000_000_000);
+    let (start, end) = (network.edge_source(edge), network.edge_target(edge));
+    assert!(network.add_shortcut(start, end, 1 << 32).is_err());
+    assert_eq!(network.edge_len(), 1);
+}
+
+#[test]
+fn aos_network_rejects_distances_it_can_not_store() {
+    build_long_edge(AoSNetworkBuilder::new());
+}
+
+/// A file in the temporary directory that only this test process uses.
+fn temporary_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("dutchroad_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn mapped_network_written_from_lite_network() {
+    let mut lite = build(LiteNetworkBuilder::new());
+    let shortcut = lite
+        .add_shortcut(NodeId::new(0), NodeId::new(6), 4000)
+        .unwrap();
+    // More decimetres than an f32 holds exactly.
+    let long = lite
+        .add_shortcut(NodeId::new(6), NodeId::new(0), (1 << 24) + 1)
+        .unwrap();
+    let path = temporary_file("written.map");
+    MappedNetwork::write(&lite, &path).unwrap();
+    let mapped = MappedNetwork::open(&path).unwrap();
+
+    assert_eq!(mapped.edge_metadata(shortcut), None);
+    assert_eq!(mapped.edge_decimetres(long), (1 << 24) + 1);
+    for edge in (0..lite.edge_len()).map(EdgeId::new) {
+        assert_eq!(mapped.edge_decimetres(edge), lite.edge_decimetres(edge));
+        assert_eq!(mapped.edge_source(edge), lite.edge_source(edge));
+        assert_eq!(mapped.edge_target(edge), lite.edge_target(edge));
+        assert_eq!(mapped.edge_metadata(edge), lite.edge_metadata(edge));
+    }
+    for node in (0..lite.nodes_len()).map(NodeId::new) {
+        assert_eq!(mapped.outgoing_edges(node), lite.outgoing_edges(node));
+        assert_eq!(mapped.incoming_edges(node), lite.incoming_edges(node));
+    }
+    drop(mapped);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn mapped_network_passes() {
+    let path = temporary_file("passes.map");
+    MappedNetwork::write(&build(AoSNetworkBuilder::new()), &path).unwrap();
+    check_all(&MappedNetwork::open(&path).unwrap());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn mapped_network_rejects_other_files() {
+    let path = temporary_file("rejects.map");
+    MappedNetwork::write(&build(LiteNetworkBuilder::new()), &path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+
+    fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    assert!(MappedNetwork::open(&path).is_err());
+    fs::write(&path, b"not a network").unwrap();
+    assert!(MappedNetwork::open(&path).is_err());
+    fs::remove_file(&path).unwrap();
+    assert!(MappedNetwork::open(&path).is_err());
+}
+
+/// Files with the right length whose header or arrays are damaged are errors, not panics.
+#[test]
+fn mapped_network_rejects_damaged_files() {
+    let network = build(LiteNetworkBuilder::new());
+    let path = temporary_file("damaged.map");
+    MappedNetwork::write(&network, &path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+
+    // Byte positions of the arrays after the 48 byte header, in the order of the file.
+    let (nodes, edges) = (network.nodes_len(), network.edge_len());
+    let outgoing_offsets = 48 + nodes * (size_of::<u32>() + size_of::<NodeCoord>());
+    let outgoing_edges = outgoing_offsets + (nodes + 1) * size_of::<u32>();
+    let incoming_offsets = outgoing_edges + edges * size_of::<EdgeId>();
+    let target = incoming_offsets + (nodes + 1) * size_of::<u32>() + 2 * edges * size_of::<u32>();
+    let opens_with = |position: usize, value: &[u8]| {
+        let mut damaged = bytes.clone();
+        damaged[position..position + value.len()].copy_from_slice(value);
+        fs::write(&path, damaged).unwrap();
+        MappedNetwork::open(&path).is_ok()
+    };
+
+    assert!(opens_with(0, &bytes[..8]));
+    // Later nodes have edges, so the edges of node 1 can not start after all edges.
+    assert!(!opens_with(
+        outgoing_offsets + 4,
+        &(edges as u32).to_ne_bytes()
+    ));
+    assert!(!opens_with(
+        incoming_offsets + 4,
+        &(edges as u32).to_ne_bytes()
+    ));
+    let last = nodes * size_of::<u32>();
+    assert!(!opens_with(
+        outgoing_offsets + last,
+        &(edges as u32 + 1).to_ne_bytes()
+    ));
+    assert!(!opens_with(outgoing_edges, &(edges as u32).to_ne_bytes()));
+    assert!(!opens_with(target, &(nodes as u32).to_ne_bytes()));
+    // A node count whose arrays would not fit in memory.
+    assert!(!opens_with(16, &(u64::MAX / 4).to_ne_bytes()));
+    assert!(!opens_with(16, &(u64::from(u32::MAX) + 1).to_ne_bytes()));
+    fs::remove_file(&path).unwrap();
+}
+
+/// Why the file at `path` would be built again, `None` when it is current.
+fn stale_reason<S: Layout + DeserializeOwned>(
+    path: &Path,
+    options: &BuildOptions,
+    source: SourceCheck,
+) -> Option<String> {
+    match file::read_network::<S, _>(path, options, source).unwrap() {
+        NetworkFile::Current(_) => None,
+        NetworkFile::Stale(reason) => Some(reason),
+    }
+}
+
+#[test]
+fn network_file_is_read_back() {
+    let path = temporary_file("current.axe");
+    let options = BuildOptions::default();
+    let source = Source {
+        hash: 1,
+        files: Vec::new(),
+    };
+    let network = build(LiteNetworkBuilder::new());
+    network.write(&path, &options, &source).unwrap();
+
+    match LiteNetwork::read(&path, &options, SourceCheck::Network(&source)).unwrap() {
+        NetworkFile::Current(network) => check_all(&network),
+        NetworkFile::Stale(reason) => panic!("stale: {}", reason),
+    }
+    assert_eq!(file::read_source(&path).unwrap(), source);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn network_file_is_stale_when_built_differently() {
+    let path = temporary_file("stale.axe");
+    let options = BuildOptions::default();
+    let stamp = FileStamp {
+        size: 100,
+        modified: SystemTime::UNIX_EPOCH,
+    };
+    let source = Source {
+        hash: 1,
+        files: vec![stamp],
+    };
+    file::write_network(&build(LiteNetworkBuilder::new()), &path, &options, &source).unwrap();
+    let stale = |options: &BuildOptions, source: SourceCheck<'_>| {
+        stale_reason::<LiteNetwork>(&path, options, source)
+    };
+    assert_eq!(stale(&options, SourceCheck::Unknown), None);
+    assert_eq!(stale(&options, SourceCheck::Files(&[stamp])), None);
+
+    let reordered = BuildOptions {
+        reorder: Some(NodeOrder::Hilbert),
+        ..BuildOptions::default()
+    };
+    assert_eq!(
+        stale(&reordered, SourceCheck::Unknown).unwrap(),
+        "built with node order None, expected Some(Hilbert)"
+    );
+
+    let changed = "the source data changed".to_string();
+    let touched = FileStamp {
+        modified: SystemTime::UNIX_EPOCH + Duration::from_secs(1),
+        ..stamp
+    };
+    assert_eq!(
+        stale(&options, SourceCheck::Files(&[touched])),
+        Some(changed.clone())
+    );
+    let rehashed = Source {
+        hash: 2,
+        ..source.clone()
+    };
+    assert_eq!(
+        stale(&options, SourceCheck::Network(&rehashed)),
+        Some(changed)
+    );
+
+    assert_eq!(
+        stale_reason::<HighwayHierarchy>(&path, &options, SourceCheck::Unknown).unwrap(),
+        "contains a lite network, expected a highway hierarchy"
+    );
+
+    // The version follows the magic number.
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[8..12].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    fs::write(&path, &bytes).unwrap();
+    assert_eq!(
+        stale(&options, SourceCheck::Unknown).unwrap(),
+        format!(
+            "format version {}, expected {}",
+            FORMAT_VERSION + 1,
+            FORMAT_VERSION
+        )
+    );
+    assert!(file::read_source(&path).is_err());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn headerless_network_file_is_stale() {
+    let path = temporary_file("headerless.axe");
+    // As networks were written before they had a header.
+    let network = build(LiteNetworkBuilder::new());
+    fs::write(&path, bincode::serialize(&network).unwrap()).unwrap();
+
+    let options = BuildOptions::default();
+    assert_eq!(
+        stale_reason::<LiteNetwork>(&path, &options, SourceCheck::Unknown).unwrap(),
+        "not a network file, or written before network files had a header"
+    );
+    // Stale, so it would be rebuilt if there was a shapefile.
+    let missing = temporary_file("missing.shp");
+    let error = preprocess::preprocess_network(&missing, &path, &options).unwrap_err();
+    assert!(error.to_string().contains("is stale"), "{}", error);
+    fs::remove_file(path).unwrap();
+}
+
+/// Checks that the reordered copy of `network` is the same network with other ids.
+fn check_reordered(network: &LiteNetwork, order: NodeOrder) {
+    let (reordered, renumbering) = network.reordered(order);
+    let nodes = (0..network.nodes_len()).map(NodeId::new);
+    let edges = (0..network.edge_len()).map(EdgeId::new);
+    assert_eq!(reordered.nodes_len(), network.nodes_len());
+    assert_eq!(reordered.edge_len(), network.edge_len());
+    assert!(reordered.is_compact());
+
+    // Every new id is the new id of exactly one old id.
+    let mut new_nodes = nodes
+        .clone()
+        .map(|node| renumbering.node(node))
+        .collect::<Vec<_>>();
+    new_nodes.sort_unstable();
+    assert!(new_nodes.iter().copied().eq(nodes.clone()));
+    let mut new_edges = edges
+        .clone()
+        .map(|edge| renumbering.edge(edge))
+        .collect::<Vec<_>>();
+    new_edges.sort_unstable();
+    assert!(new_edges.iter().copied().eq(edges.clone()));
+
+    for node in nodes.clone() {
+        let new = renumbering.node(node);
+        let location = network.node_location(node);
+        assert_eq!(reordered.junction_id(new), network.junction_id(node));
+        assert_eq!(reordered.node_location(new), location);
+        assert_eq!(closest_node(&reordered, location), new);
+
+        let renumbered = |edges: &[EdgeId]| {
+            let mut edges = edges
+                .iter()
+                .map(|edge| renumbering.edge(*edge))
+                .collect::<Vec<_>>();
+            edges.sort_unstable();
+            edges
+        };
+        assert_eq!(
+            reordered.outgoing_edges(new),
+            renumbered(network.outgoing_edges(node))
+        );
+        let mut incoming = reordered.incoming_edges(new).to_vec();
+        incoming.sort_unstable();
+        assert_eq!(incoming, renumbered(network.incoming_edges(node)));
+    }
+
+    for edge in edges {
+        let new = renumbering.edge(edge);
+        let source = renumbering.node(network.edge_source(edge));
+        assert_eq!(reordered.edge_source(new), source);
+        let target = renumbering.node(network.edge_target(edge));
+        assert_eq!(reordered.edge_target(new), target);
+        assert_eq!(reordered.edge_object_id(new), network.edge_object_id(edge));
+        assert_eq!(
+            reordered.edge_decimetres(new),
+            network.edge_decimetres(edge)
+        );
+        assert_eq!(reordered.edge_metadata(new), network.edge_metadata(edge));
+    }
+
+    // The edges are numbered in the order of their new source node.
+    let outgoing = (0..reordered.nodes_len())
+        .flat_map(|node| reordered.outgoing_edges(NodeId::new(node)).to_vec())
+        .collect::<Vec<_>>();
+    assert!(outgoing
+        .iter()
+        .copied()
+        .eq((0..outgoing.len()).map(EdgeId::new)));
+
+    for start in nodes.step_by(7) {
+        let old = DijkstraIterator::new(network, start, DijkstraDirection::Forward)
+            .map(|(cost, node)| (renumbering.node(node), cost))
+            .collect::<HashMap<_, _>>();
+        let new = renumbering.node(start);
+        let new = DijkstraIterator::new(&reordered, new, DijkstraDirection::Forward)
+            .map(|(cost, node)| (node, cost))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(new, old);
+    }
+}
+
+#[test]
+fn reordered_network_is_the_same_network() {
+    let mut network = build(LiteNetworkBuilder::new());
+    network
+        .add_shortcut(NodeId::new(0), NodeId::new(6), 4000)
+        .unwrap();
+    // A disconnected edge keeps its place after the others.
+    network.disconnect_edge(EdgeId::new(3));
+    let networks = [
+        network,
+        generate::grid(LiteNetworkBuilder::new(), 12, 9, 100.0),
+        generate::random_geometric(LiteNetworkBuilder::new(), 300, 2000.0, 250.0, 7),
+    ];
+
+    for network in &networks {
+        for order in [NodeOrder::Hilbert, NodeOrder::BreadthFirst] {
+            check_reordered(network, order);
+        }
+    }
+}
+
+#[test]
+fn lite_network_keeps_metadata_when_reordered() {
+    let mut network = build(LiteNetworkBuilder::new());
+    let shortcut = network
+        .add_shortcut(NodeId::new(0), NodeId::new(6), 4000)
+        .unwrap();
+    assert_eq!(network.edge_metadata(shortcut), None);
+
+    let (reordered, renumbering) = network.reordered(NodeOrder::Hilbert);
+    for edge in (0..network.edge_len()).map(EdgeId::new) {
+        let new = renumbering.edge(edge);
+        assert_eq!(reordered.edge_metadata(new), network.edge_metadata(edge));
+    }
+}
+
+#[test]
+fn object_index_finds_the_edges_of_a_road() {
+    let mut network = build(LiteNetworkBuilder::new());
+    network
+        .add_shortcut(NodeId::new(0), NodeId::new(6), 4000)
+        .unwrap();
+    let (network, _) = network.reordered(NodeOrder::BreadthFirst);
+    let index = ObjectIndex::new(&network);
+
+    let junction = |node| network.junction_id(node);
+    for (object_id, start, end, .., direction) in ROADS.iter() {
+        let mut ends = index
+            .edges(*object_id)
+            .iter()
+            .map(|edge| {
+                assert_eq!(network.edge_object_id(*edge), *object_id);
+                (
+                    junction(network.edge_source(*edge)),
+                    junction(network.edge_target(*edge)),
+                )
+            })
+            .collect::<Vec<_>>();
+        ends.sort_unstable();
+
+        let (start, end) = (JUNCTIONS[*start].0, JUNCTIONS[*end].0);
+        let mut expected = match direction.parse().unwrap() {
+            RoadDirection::BOTH => vec![(start, end), (end, start)],
+            RoadDirection::WITH => vec![(start, end)],
+            RoadDirection::AGAINST => vec![(end, start)],
+        };
+        expected.sort_unstable();
+        assert_eq!(ends, expected);
+    }
+    assert!(index.edges(ROADS.len() as u32).is_empty());
+}
+
+/// Checks that the rows of `adjacency` are `rows` and that the room of the rows does not overlap.
+fn check_rows(adjacency: &Adjacency, rows: &[Vec<EdgeId>]) {
+    if adjacency.is_compact() {
+        let mut start = vec![0];
+        for (node, row) in rows.iter().enumerate() {
+            assert_eq!(adjacency.get(NodeId::new(node)), row.as_slice());
+            start.push(start[node] + row.len() as u32);
+        }
+        assert_eq!(adjacency.start, start);
+        assert_eq!(adjacency.edges.len(), start[rows.len()] as usize);
+        return;
+    }
+
+    let mut room = Vec::new();
+    for (node, row) in rows.iter().enumerate() {
+        let node = NodeId::new(node);
+        assert_eq!(adjacency.get(node), row.as_slice());
+        assert!(adjacency.len[node] <= adjacency.capacity[node]);
+        room.push(adjacency.start[node]..adjacency.start[node] + adjacency.capacity[node]);
+    }
+
+    room.retain(|room| !room.is_empty());
+    room.sort_by_key(|room| room.start);
+    for pair in room.windows(2) {
+        assert!(pair[0].end <= pair[1].start);
+    }
+    if let Some(last) = room.last() {
+        assert!(last.end as usize <= adjacency.edges.len());
+    }
+}
+
+#[test]
+fn adjacency_grows_rows_and_compacts() {
+    let mut adjacency = Adjacency::default();
+    let mut rows = vec![Vec::new(); 4];
+    for _ in &rows {
+        adjacency.add_node();
+    }
+
+    // Edges are added to the nodes in turn, so most rows fill up while another row is after them.
+    for edge in (0..40).map(EdgeId::new) {
+        let node = [0, 1, 2, 0, 3, 0, 1][edge.index() % 7];
+        adjacency.push(NodeId::new(node), edge);
+        rows[node].push(edge);
+        check_rows(&adjacency, &rows);
+    }
+
+    adjacency.retain(NodeId::new(0), |edge| edge.index() % 2 == 0);
+    rows[0].retain(|edge| edge.index() % 2 == 0);
+    assert_eq!(adjacency.take(NodeId::new(1)), rows[1]);
+    rows[1].clear();
+    check_rows(&adjacency, &rows);
+
+    adjacency.compact();
+    assert!(adjacency.is_compact());
+    assert!(adjacency.len.is_empty() && adjacency.capacity.is_empty());
+    check_rows(&adjacency, &rows);
+
+    // Only the offsets and the edges are serialized, growing rows are serialized compacted.
+    let edges = rows.iter().map(Vec::len).sum::<usize>();
+    let encoded = bincode::serialize(&adjacency).unwrap();
+    assert_eq!(encoded.len(), 8 + 4 * (rows.len() + 1) + 8 + 4 * edges);
+    let decoded: Adjacency = bincode::deserialize(&encoded).unwrap();
+    check_rows(&decoded, &rows);
+    let mut growing = adjacency.clone();
+    growing.retain(NodeId::new(2), |_| true);
+    assert!(!growing.is_compact());
+    assert_eq!(bincode::serialize(&growing).unwrap(), encoded);
+
+    // New nodes keep the rows compact.
+    adjacency.add_node();
+    rows.push(Vec::new());
+    assert!(adjacency.is_compact());
+    check_rows(&adjacency, &rows);
+
+    // Rows still grow after compacting, also the rows that were emptied.
+    for edge in (40..60).map(EdgeId::new) {
+        let node = edge.index() % rows.len();
+        adjacency.push(NodeId::new(node), edge);
+        rows[node].push(edge);
+        check_rows(&adjacency, &rows);
+    }
+}
+
+fn record(fields: &[(&str, &str)]) -> HashMap<String, FieldValue> {
+    fields
+        .iter()
+        .map(|(field, value)| {
+            let value = FieldValue::Character(Some(value.to_string()));
+            (field.to_string(), value)
+        })
+        .collect()
+}
+
+#[test]
+fn speed_table_takes_the_first_matching_rule() {
+    let table: SpeedTable = "
+        # Roundabouts first.
+        BST_CODE=RB 30
+        WEGBEHSRT=R WEGNUMMER=* 100  # numbered state roads
+        WEGBEHSRT=R 80
+    "
+    .parse()
+    .unwrap();
+
+    let speed = |fields| table.speed(&record(fields));
+    assert_eq!(speed(&[("BST_CODE", "RB"), ("WEGBEHSRT", "R")]), Some(30));
+    assert_eq!(
+        speed(&[("WEGBEHSRT", "R"), ("WEGNUMMER", "004")]),
+        Some(100)
+    );
+    assert_eq!(speed(&[("WEGBEHSRT", "R"), ("WEGNUMMER", " ")]), Some(80));
+    assert_eq!(speed(&[("WEGBEHSRT", "R")]), Some(80));
+    assert_eq!(speed(&[("WEGBEHSRT", "G")]), None);
+
+    // The default table has a speed for every road.
+    let table = SpeedTable::default();
+    assert_eq!(table.speed(&record(&[("WEGBEHSRT", "G")])), Some(50));
+    assert_eq!(table.speed(&HashMap::new()), Some(50));
+}
+
+#[test]
+fn speed_table_rejects_invalid_rules() {
+    for text in [
+        "BST_CODE=RB",
+        "BST_CODE=RB 0",
+        "BST_CODE=RB 300",
+        "BST_CODE 30",
+    ] {
+        assert!(text.parse::<SpeedTable>().is_err(), "{}", text);
+    }
+}
+
+fn part(start: f32, end: f32, side: &str, value: u8) -> Part {
+    let side = side.parse().unwrap();
+    Part {
+        start,
+        end,
+        side,
+        value,
+    }
+}
+
+#[test]
+fn road_attributes_join_by_position_and_side() {
+    let mut attributes = RoadAttributes::default();
+    let speeds = &mut attributes.max_speeds;
+    // The first 100 metres at 50 km/h and the rest at 100 km/h, against the wegvak only the first
+    // half is known. Parts may run backwards and past the end of the road.
+    speeds.insert(1, part(100.0, 0.0, "B", 50));
+    speeds.insert(1, part(0.0, 400.0, "H", 100));
+    speeds.insert(1, part(100.0, 150.0, "T", 100));
+    attributes.lanes.insert(1, part(200.0, 300.0, "H", 1));
+    attributes.lanes.insert(1, part(0.0, 300.0, "B", 2));
+
+    let (with, against) = (RoadDirection::WITH, RoadDirection::AGAINST);
+    assert_eq!(
+        attributes.max_speeds.along(1, 300.0, with),
+        vec![(100.0, 50), (200.0, 100)]
+    );
+    assert_eq!(
+        attributes.max_speeds.along(1, 300.0, against),
+        vec![(100.0, 50), (50.0, 100)]
+    );
+    assert!(attributes.max_speeds.along(2, 300.0, with).is_empty());
+
+    // 300 metres in the time of 100 metres at 50 km/h and 200 metres at 100 km/h.
+    assert_eq!(attributes.speed(1, 300.0, with, 30), 75);
+    // The unknown 150 metres at 30 km/h.
+    assert_eq!(attributes.speed(1, 300.0, against, 30), 40);
+    assert_eq!(attributes.speed(2, 300.0, with, 30), 30);
+    assert_eq!(attributes.speed(1, 0.0, with, 30), 30);
+
+    assert_eq!(attributes.lanes(1, 300.0, with), 1);
+    assert_eq!(attributes.lanes(1, 300.0, against), 2);
+    assert_eq!(attributes.lanes(2, 300.0, with), 0);
+}
@@ -1,8 +1,9 @@
 use std::{collections::HashMap, str::FromStr};
 
-use shapefile::{Polyline, dbase::FieldValue};
+use shapefile::{dbase::FieldValue, Polyline};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum RoadDirection {
     // Both (JTE_BEGIN <-> JTE_END) denoted with H
     BOTH,
@@ -43,6 +44,24 @@ pub fn get_numeric(record: &HashMap<String, FieldValue>, key: &str) -> Result<f6
     }
 }
 
+/// Checks that a junction id read from the shapefile fits in 32 bits.
+pub fn junction_id(value: f64) -> Result<u32, String> {
+    whole_u32(value).ok_or_else(|| format!("Junction id {} does not fit in 32 bits", value))
+}
+
+/// Checks that a wegvak id read from a shapefile fits in 32 bits.
+pub fn wegvak_id(value: f64) -> Result<u32, String> {
+    whole_u32(value).ok_or_else(|| format!("Wegvak id {} does not fit in 32 bits", value))
+}
+
+fn whole_u32(value: f64) -> Option<u32> {
+    if value >= 0.0 && value <= u32::MAX as f64 && value.fract() == 0.0 {
+        Some(value as u32)
+    } else {
+        None
+    }
+}
+
 pub fn calculate_distance(shape: &Polyline) -> f32 {
     let mut sum = 0f32;
 
@@ -60,4 +79,4 @@ pub fn calculate_distance(shape: &Polyline) -> f32 {
     }
 
     sum
-}
\ No newline at end of file
+}
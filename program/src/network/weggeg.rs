@@ -0,0 +1,151 @@
+// Importer of the wegkenmerken (weggeg) shapefiles of Rijkswaterstaat, which describe parts of the
+// wegvakken of the NWB. A record gives a value, such as a maximum speed or a number of lanes, to
+// the part of wegvak `WVK_ID` from `BEGAFSTAND` to `ENDAFSTAND` metres along its shape, on the side
+// `KANTCODE`: `H` in the direction of the wegvak, `T` against it and both otherwise. The value is
+// the first number in the description `OMSCHR`, records without one are skipped.
+//
+// The NWB importer joins the values onto the edges of every wegvak by `RoadAttributes`.
+use super::{consts::*, utils::*};
+use shapefile::dbase::{FieldValue, Record};
+use std::{collections::HashMap, error::Error, path::Path};
+
+/// The value of part of a wegvak.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    /// Metres along the shape of the wegvak where the part starts.
+    pub start: f32,
+    pub end: f32,
+    /// The directions of travel the value holds for.
+    pub side: RoadDirection,
+    pub value: u8,
+}
+
+/// The parts of wegvakken that have a value for one wegkenmerk.
+#[derive(Debug, Clone, Default)]
+pub struct RoadAttribute {
+    parts: HashMap<u32, Vec<Part>>,
+}
+
+impl RoadAttribute {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut attribute = Self::default();
+        for record in shapefile::Reader::from_path(path)?.read_records()? {
+            let value = match first_number(&record, DESCRIPTION) {
+                Some(value) if value > 0 => value,
+                _ => continue,
+            };
+            let side = match record.get(SIDE) {
+                Some(FieldValue::Character(Some(side))) => {
+                    side.trim().parse().unwrap_or(RoadDirection::BOTH)
+                }
+                _ => RoadDirection::BOTH,
+            };
+
+            let wegvak = wegvak_id(get_numeric(&record, WEGVAK_ID)?)?;
+            let part = Part {
+                start: get_numeric(&record, PART_START)? as f32,
+                end: get_numeric(&record, PART_END)? as f32,
+                side,
+                value,
+            };
+            attribute.insert(wegvak, part);
+        }
+        Ok(attribute)
+    }
+
+    pub fn insert(&mut self, wegvak: u32, part: Part) {
+        self.parts.entry(wegvak).or_default().push(part);
+    }
+
+    /// The stretches of wegvak `wegvak` of `length` metres that have a value when it is driven in
+    /// `direction`, as their length and value in the order of the wegvak. Where parts overlap the
+    /// part that was added first counts.
+    pub fn along(&self, wegvak: u32, length: f32, direction: RoadDirection) -> Vec<(f32, u8)> {
+        let parts = match self.parts.get(&wegvak) {
+            Some(parts) => parts
+                .iter()
+                .filter(|part| part.side == RoadDirection::BOTH || part.side == direction)
+                .map(|part| {
+                    let (start, end) = (part.start.min(part.end), part.start.max(part.end));
+                    (start.clamp(0.0, length), end.clamp(0.0, length), part.value)
+                })
+                .collect::<Vec<_>>(),
+            None => return Vec::new(),
+        };
+
+        let mut bounds = parts
+            .iter()
+            .flat_map(|(start, end, _)| [*start, *end])
+            .collect::<Vec<_>>();
+        bounds.sort_by(f32::total_cmp);
+        bounds.dedup();
+
+        bounds
+            .windows(2)
+            .filter_map(|stretch| {
+                let middle = (stretch[0] + stretch[1]) / 2.0;
+                parts
+                    .iter()
+                    .find(|(start, end, _)| *start < middle && middle < *end)
+                    .map(|(_, _, value)| (stretch[1] - stretch[0], *value))
+            })
+            .collect()
+    }
+}
+
+/// The wegkenmerken that are joined onto the edges of the network, a wegkenmerk that is not read
+/// has no parts.
+#[derive(Debug, Clone, Default)]
+pub struct RoadAttributes {
+    /// Maximum speeds in km/h.
+    pub max_speeds: RoadAttribute,
+    /// Number of lanes in the direction of travel.
+    pub lanes: RoadAttribute,
+}
+
+impl RoadAttributes {
+    /// Average speed in km/h of driving wegvak `wegvak` of `length` metres in `direction`, at the
+    /// maximum speed where it is known and at `speed` elsewhere.
+    pub fn speed(&self, wegvak: u32, length: f32, direction: RoadDirection, speed: u8) -> u8 {
+        let stretches = self.max_speeds.along(wegvak, length, direction);
+        let known: f32 = stretches.iter().map(|(length, _)| length).sum();
+        // In metres per km/h, which is proportional to the time it takes.
+        let time = (length - known).max(0.0) / f32::from(speed)
+            + stretches
+                .iter()
+                .map(|(length, speed)| length / f32::from(*speed))
+                .sum::<f32>();
+
+        if time > 0.0 {
+            (length / time).round().clamp(1.0, f32::from(u8::MAX)) as u8
+        } else {
+            speed
+        }
+    }
+
+    /// The fewest lanes along wegvak `wegvak` of `length` metres in `direction`, 0 when no number
+    /// of lanes is known.
+    pub fn lanes(&self, wegvak: u32, length: f32, direction: RoadDirection) -> u8 {
+        self.lanes
+            .along(wegvak, length, direction)
+            .iter()
+            .map(|(_, lanes)| *lanes)
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// The value of a numeric field, or the first whole number in a text field.
+fn first_number(record: &Record, field: &str) -> Option<u8> {
+    match record.get(field)? {
+        FieldValue::Numeric(Some(value)) if (0.0..=f64::from(u8::MAX)).contains(value) => {
+            Some(value.round() as u8)
+        }
+        FieldValue::Character(Some(text)) => text
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|number| !number.is_empty())?
+            .parse()
+            .ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,139 @@
+use crate::network::{EdgeId, Network, NodeId};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+#[derive(Debug, Clone)]
+pub struct OverlayEdge {
+    pub target: NodeId,
+    /// Decimetres, the sum of the edges of `path`.
+    pub distance: u64,
+    // The path in the overlay graph one level below.
+    pub path: Vec<EdgeId>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OverlaySearch {
+    pub edges: Vec<OverlayEdge>,
+    // Every node the search settled, a change to their edges can change the overlay edges.
+    pub settled: Vec<NodeId>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CoverLabel {
+    distance: u64,
+    // A highway node of the new level lies between the source and this node.
+    covered: bool,
+    edge: Option<EdgeId>,
+    settled: bool,
+}
+
+/// The overlay edges of `level` leaving `source`, found by a search in the overlay graph of the
+/// level below.
+///
+/// Nodes behind another highway node of `level` are covered, the search stops when only covered
+/// nodes are left in the queue. With equal distances uncovered paths are preferred, every highway
+/// node that is settled uncovered gets an overlay edge.
+pub fn overlay_edges<S: Network>(
+    network: &S,
+    edge_level: &[u8],
+    node_level: &[u8],
+    level: u8,
+    source: NodeId,
+) -> OverlaySearch {
+    let mut labels: HashMap<NodeId, CoverLabel> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+    let mut uncovered_queued = 1;
+    let mut found = Vec::new();
+    let mut settled = Vec::new();
+
+    labels.insert(
+        source,
+        CoverLabel {
+            distance: 0,
+            covered: false,
+            edge: None,
+            settled: false,
+        },
+    );
+    heap.push(Reverse((0, false, source)));
+
+    while uncovered_queued > 0 {
+        let (distance, covered, node) = match heap.pop() {
+            Some(Reverse((distance, covered, node))) => (distance, covered, node),
+            None => break,
+        };
+        if !covered {
+            uncovered_queued -= 1;
+        }
+
+        let label = labels[&node];
+        if label.settled || label.distance != distance || label.covered != covered {
+            continue;
+        }
+        labels.get_mut(&node).unwrap().settled = true;
+        settled.push(node);
+
+        let is_highway_node = node != source && node_level[node.index()] >= level;
+        if is_highway_node && !covered {
+            found.push(OverlayEdge {
+                target: node,
+                distance,
+                path: rebuild(network, &labels, node),
+            });
+        }
+
+        for edge in network.outgoing_edges(node) {
+            if edge_level[edge.index()] != level - 1 {
+                continue;
+            }
+
+            let target = network.edge_target(*edge);
+            let new_label = CoverLabel {
+                distance: distance + u64::from(network.edge_decimetres(*edge)),
+                covered: covered || is_highway_node,
+                edge: Some(*edge),
+                settled: false,
+            };
+
+            match labels.get_mut(&target) {
+                Some(existing)
+                    if existing.settled
+                        || (existing.distance, existing.covered)
+                            <= (new_label.distance, new_label.covered) =>
+                {
+                    continue
+                }
+                Some(existing) => *existing = new_label,
+                None => {
+                    labels.insert(target, new_label);
+                }
+            }
+
+            if !new_label.covered {
+                uncovered_queued += 1;
+            }
+            heap.push(Reverse((new_label.distance, new_label.covered, target)));
+        }
+    }
+
+    OverlaySearch {
+        edges: found,
+        settled,
+    }
+}
+
+fn rebuild<S: Network>(
+    network: &S,
+    labels: &HashMap<NodeId, CoverLabel>,
+    mut node: NodeId,
+) -> Vec<EdgeId> {
+    let mut edges = Vec::new();
+    while let Some(edge) = labels[&node].edge {
+        edges.push(edge);
+        node = network.edge_source(edge);
+    }
+    edges.reverse();
+    edges
+}
@@ -0,0 +1,233 @@
+// Highway node routing, following "Dynamic Highway-Node Routing" (Schultes, Sanders).
+//
+// The nodes are ranked by importance and every level l > 0 has a set of highway nodes S_l: the
+// first nodes of the ranking, so S_l is a subset of S_(l - 1). The overlay graph of level l has an
+// edge (s, t) for s, t in S_l when a shortest path from s to t in the overlay graph of level l - 1
+// has no inner node in S_l. Level 0 is the original network with all nodes.
+//
+// Every node a construction search settled is remembered, when the distance of an edge changes
+// only the searches that relaxed it have to be repeated.
+pub mod construction;
+pub mod update;
+
+use crate::{
+    algorithm::{dijkstra::DijkstraDirection, EdgePath},
+    network::{
+        builder::DistanceError, file::Layout, EdgeId, LiteNetwork, Network, NodeId, Shortcuts,
+    },
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MultiLevelOverlay {
+    // The original network, the overlay edges of all levels are appended as shortcuts.
+    network: LiteNetwork,
+    shortcuts: Shortcuts,
+    // For every node the highest level it is a highway node of.
+    node_level: Vec<u8>,
+    // For every edge the overlay graph it belongs to, 0 for the original edges.
+    edge_level: Vec<u8>,
+    top_level: u8,
+    // The search spaces of the construction searches of every level above 0.
+    search_spaces: Vec<SearchSpaces>,
+}
+
+impl Layout for MultiLevelOverlay {
+    const LAYOUT: &'static str = "multi-level overlay";
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct SearchSpaces {
+    // Highway node -> the nodes its construction search settled.
+    settled: HashMap<NodeId, Vec<NodeId>>,
+    // Node -> the highway nodes whose construction search settled it.
+    settled_by: HashMap<NodeId, Vec<NodeId>>,
+}
+
+impl MultiLevelOverlay {
+    /// Builds the overlay graphs, level l has the first `level_sizes[l - 1]` nodes of `ranking`
+    /// as highway nodes. Fails when an overlay edge is too long to store.
+    pub fn build(
+        network: LiteNetwork,
+        ranking: &[NodeId],
+        level_sizes: &[usize],
+    ) -> Result<Self, DistanceError> {
+        assert!(
+            level_sizes.windows(2).all(|sizes| sizes[0] >= sizes[1]),
+            "higher levels can not have more highway nodes"
+        );
+        assert!(level_sizes.len() < u8::MAX as usize, "too many levels");
+
+        let mut node_level = vec![0u8; network.nodes_len()];
+        for (level, size) in level_sizes.iter().enumerate() {
+            for node in &ranking[..*size] {
+                node_level[*node] = level as u8 + 1;
+            }
+        }
+
+        let mut overlay = Self {
+            shortcuts: Shortcuts::new(network.edge_len()),
+            edge_level: vec![0; network.edge_len()],
+            network,
+            node_level,
+            top_level: level_sizes.len() as u8,
+            search_spaces: vec![SearchSpaces::default(); level_sizes.len()],
+        };
+
+        for level in 1..=overlay.top_level {
+            let nodes = overlay.level_nodes(level);
+            overlay.search_level(level, &nodes)?;
+        }
+
+        overlay.network.compact();
+        Ok(overlay)
+    }
+
+    /// The network with all overlay edges, paths on it can be unpacked with `unpack_path`.
+    pub fn network(&self) -> &LiteNetwork {
+        &self.network
+    }
+
+    pub fn shortcuts(&self) -> &Shortcuts {
+        &self.shortcuts
+    }
+
+    pub fn top_level(&self) -> u8 {
+        self.top_level
+    }
+
+    pub fn node_level(&self, node: NodeId) -> u8 {
+        self.node_level[node]
+    }
+
+    pub fn edge_level(&self, edge: EdgeId) -> u8 {
+        self.edge_level[edge]
+    }
+
+    /// The highway nodes of `level`.
+    pub fn level_nodes(&self, level: u8) -> Vec<NodeId> {
+        (0..self.node_level.len())
+            .map(NodeId::new)
+            .filter(|node| self.node_level[*node] >= level)
+            .collect()
+    }
+
+    /// The edges of the overlay graph of `level` that leave (forward) or enter (backward) `node`,
+    /// together with the node on the other end.
+    pub fn level_neighbours<'a>(
+        &'a self,
+        node: NodeId,
+        level: u8,
+        direction: &DijkstraDirection,
+    ) -> impl Iterator<Item = (NodeId, EdgeId)> + 'a {
+        direction
+            .neighbours(node, &self.network)
+            .filter(move |(_, edge)| self.edge_level[*edge] == level)
+    }
+
+    /// Replaces the overlay edges in a path by the original edges.
+    pub fn unpack_path(&self, path: &EdgePath) -> EdgePath {
+        self.shortcuts.unpack_path(path)
+    }
+
+    /// Repeats the construction searches from `sources` on `level` and replaces their overlay
+    /// edges. Returns the sources whose overlay edges changed. Fails when an overlay edge is too
+    /// long to store, the overlay edges of the sources before it are already replaced.
+    fn search_level(
+        &mut self,
+        level: u8,
+        sources: &[NodeId],
+    ) -> Result<Vec<NodeId>, DistanceError> {
+        let searches = sources
+            .par_iter()
+            .map(|source| {
+                construction::overlay_edges(
+                    &self.network,
+                    &self.edge_level,
+                    &self.node_level,
+                    level,
+                    *source,
+                )
+            })
+            .collect::<Vec<_>>();
+        let old_edges = self.nodes_edges(sources, level);
+
+        let mut changed = Vec::new();
+        for ((source, search), old_edges) in sources.iter().zip(searches).zip(old_edges) {
+            let construction::OverlaySearch { edges, settled } = search;
+            self.search_spaces[level as usize - 1].replace(*source, settled);
+
+            let unchanged = old_edges.len() == edges.len()
+                && old_edges.iter().all(|old| {
+                    edges.iter().any(|new| {
+                        new.target == self.network.edge_target(*old)
+                            && new.distance == u64::from(self.network.edge_decimetres(*old))
+                            && Some(new.path.as_slice()) == self.shortcuts.replaces(*old)
+                    })
+                });
+            if unchanged {
+                continue;
+            }
+
+            for edge in old_edges {
+                self.network.disconnect_edge(edge);
+            }
+            for overlay_edge in edges {
+                self.add_overlay_edge(*source, level, overlay_edge)?;
+            }
+            changed.push(*source);
+        }
+
+        Ok(changed)
+    }
+
+    /// The overlay edges of `level` leaving each of `nodes`.
+    fn nodes_edges(&self, nodes: &[NodeId], level: u8) -> Vec<Vec<EdgeId>> {
+        nodes
+            .iter()
+            .map(|node| {
+                self.network
+                    .outgoing_edges(*node)
+                    .iter()
+                    .filter(|edge| self.edge_level[**edge] == level)
+                    .cloned()
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn add_overlay_edge(
+        &mut self,
+        source: NodeId,
+        level: u8,
+        edge: construction::OverlayEdge,
+    ) -> Result<(), DistanceError> {
+        let id = self
+            .network
+            .add_shortcut(source, edge.target, edge.distance)?;
+        self.shortcuts.insert(id, &edge.path);
+        self.edge_level.push(level);
+        Ok(())
+    }
+}
+
+impl SearchSpaces {
+    fn replace(&mut self, source: NodeId, settled: Vec<NodeId>) {
+        for node in self.settled.remove(&source).unwrap_or_default() {
+            if let Some(sources) = self.settled_by.get_mut(&node) {
+                sources.retain(|x| *x != source);
+            }
+        }
+        for node in &settled {
+            self.settled_by.entry(*node).or_default().push(source);
+        }
+        self.settled.insert(source, settled);
+    }
+
+    /// The highway nodes whose construction search settled `node`.
+    fn settled_by(&self, node: NodeId) -> &[NodeId] {
+        self.settled_by.get(&node).map_or(&[], |sources| sources)
+    }
+}
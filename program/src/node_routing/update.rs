@@ -0,0 +1,51 @@
+use super::MultiLevelOverlay;
+use crate::network::{
+    builder::DistanceError, network_lite::stored_distance, EdgeId, Network, NodeId,
+};
+use std::collections::HashSet;
+
+impl MultiLevelOverlay {
+    /// Changes the distance of original edges and repairs the overlay graphs.
+    ///
+    /// A construction search can only find other overlay edges when it settled the source of a
+    /// changed edge, so only those searches are repeated. The sources that got new overlay edges
+    /// on a level change the edges of the overlay graph searched for the level above, which
+    /// repeats the searches that settled them there.
+    ///
+    /// Replaced overlay edges are disconnected but keep their id, so the network grows a bit with
+    /// every update. Nothing changes when a distance can not be stored. When a new overlay edge is
+    /// too long to store the overlay is left partly updated, and has to be built again.
+    pub fn update_edge_distances(
+        &mut self,
+        changes: &[(EdgeId, f32)],
+    ) -> Result<(), DistanceError> {
+        for (edge, distance) in changes {
+            assert!(
+                !self.shortcuts.is_shortcut(*edge),
+                "only original edges can be updated"
+            );
+            stored_distance(*distance)?;
+        }
+
+        let mut changed = HashSet::new();
+        for (edge, distance) in changes {
+            self.network.set_edge_distance(*edge, *distance)?;
+            changed.insert(self.network.edge_source(*edge));
+        }
+
+        for level in 1..=self.top_level {
+            let search_spaces = &self.search_spaces[level as usize - 1];
+            let mut sources = changed
+                .iter()
+                .flat_map(|node| search_spaces.settled_by(*node))
+                .copied()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect::<Vec<NodeId>>();
+            sources.sort();
+
+            changed = self.search_level(level, &sources)?.into_iter().collect();
+        }
+        Ok(())
+    }
+}
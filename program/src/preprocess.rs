@@ -1,37 +1,207 @@
-use crate::network::{Network, Writeable};
-use shapefile::{reader::ShapeRecordIterator, Polyline};
-use std::{error::Error, fs::File, io::BufReader, path::Path};
+use crate::network::{
+    file::{self, FileStamp, NetworkFile, Source, SourceCheck},
+    nwb,
+    reorder::NodeOrder,
+    speed::SpeedTable,
+    weggeg::{RoadAttribute, RoadAttributes},
+    LiteNetwork, LiteNetworkBuilder,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
-pub fn preprocess<S>() -> Result<S, Box<dyn Error>>
+pub const DEFAULT_SHAPEFILE: &str = "./data/Wegvakken/Wegvakken.shp";
+pub const DEFAULT_NETWORK: &str = "./data/network3.axe";
+
+/// Options that change the network that is built from the source data, a network file is only
+/// reused when it was built with the same options.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BuildOptions {
+    /// Renumbers the nodes after reading the shapefile.
+    pub reorder: Option<NodeOrder>,
+    /// Gives every road its speed.
+    pub speeds: SpeedTable,
+    /// Wegkenmerken shapefile with the maximum speeds, which take the place of the speed table
+    /// where they are known.
+    pub max_speeds: Option<PathBuf>,
+    /// Wegkenmerken shapefile with the number of lanes.
+    pub lanes: Option<PathBuf>,
+}
+
+impl BuildOptions {
+    /// How a network built with these options differs from one built with `expected`, `None`
+    /// when they are built the same.
+    pub fn difference(&self, expected: &Self) -> Option<String> {
+        let BuildOptions {
+            reorder,
+            speeds,
+            max_speeds,
+            lanes,
+        } = self;
+
+        if *reorder != expected.reorder {
+            Some(format!(
+                "node order {:?}, expected {:?}",
+                reorder, expected.reorder
+            ))
+        } else if *speeds != expected.speeds {
+            // Speed tables are too long to print.
+            Some("another speed table".to_string())
+        } else if *max_speeds != expected.max_speeds {
+            Some(format!(
+                "maximum speeds {:?}, expected {:?}",
+                max_speeds, expected.max_speeds
+            ))
+        } else if *lanes != expected.lanes {
+            Some(format!("lanes {:?}, expected {:?}", lanes, expected.lanes))
+        } else {
+            None
+        }
+    }
+
+    /// The shapefiles of the wegkenmerken, other than the Wegvakken.
+    fn shapefiles(&self) -> impl Iterator<Item = &Path> {
+        self.max_speeds
+            .iter()
+            .chain(&self.lanes)
+            .map(PathBuf::as_path)
+    }
+
+    /// The files a network is built from with the Wegvakken `shapefile`, the shapes and the
+    /// records of every shapefile.
+    fn source_files(&self, shapefile: &Path) -> Vec<PathBuf> {
+        std::iter::once(shapefile)
+            .chain(self.shapefiles())
+            .flat_map(|path| [path.to_path_buf(), path.with_extension("dbf")])
+            .collect()
+    }
+
+    fn read_attributes(&self) -> Result<RoadAttributes, Box<dyn Error>> {
+        let read = |path: &Option<PathBuf>| match path {
+            Some(path) => RoadAttribute::read(path),
+            None => Ok(RoadAttribute::default()),
+        };
+        Ok(RoadAttributes {
+            max_speeds: read(&self.max_speeds)?,
+            lanes: read(&self.lanes)?,
+        })
+    }
+}
+
+/// How `preprocess_network` got the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Preprocessed {
+    /// The network file was up to date.
+    Read,
+    /// There was no network file yet.
+    Built,
+    /// The network file was stale for the reason, it is built again.
+    Rebuilt(String),
+}
+
+/// Reads the preprocessed network at `output`, or builds it from the shapefile at `input` and the
+/// wegkenmerken of `options` when it does not exist yet or is stale.
+///
+/// A network file without a header, written before network files had one, is stale as well. A
+/// stale network can only be rebuilt when the shapefile exists, otherwise it is an error. When
+/// only the network exists it is used without checking the source data. The source data is
+/// checked by the sizes and modification times of its files, so a network is read without
+/// reading the source files.
+pub fn preprocess_network<P>(
+    input: P,
+    output: P,
+    options: &BuildOptions,
+) -> Result<(LiteNetwork, Preprocessed), Box<dyn Error>>
 where
-    S: Writeable + Network,
+    P: AsRef<Path>,
 {
-    let input = "./data/Wegvakken/Wegvakken.shp";
-    let output = "./data/network3.axe";
-    preprocess_network(input, output)
+    let (input, output) = (input.as_ref(), output.as_ref());
+    let stamps = if input.exists() {
+        let files = options.source_files(input);
+        Some(
+            files
+                .iter()
+                .map(FileStamp::new)
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    } else {
+        None
+    };
+    let source = match &stamps {
+        Some(stamps) => SourceCheck::Files(stamps),
+        None => SourceCheck::Unknown,
+    };
+
+    let preprocessed = if output.exists() {
+        match file::read_network(output, options, source)? {
+            NetworkFile::Current(network) => return Ok((network, Preprocessed::Read)),
+            NetworkFile::Stale(reason) if stamps.is_some() => Preprocessed::Rebuilt(reason),
+            NetworkFile::Stale(reason) => {
+                return Err(format!(
+                    "{:?} is stale ({}) and there is no shapefile {:?} to rebuild it",
+                    output, reason, input
+                )
+                .into());
+            }
+        }
+    } else {
+        Preprocessed::Built
+    };
+
+    Ok((build_network(input, output, options)?, preprocessed))
 }
 
-fn preprocess_network<P, S>(input: P, output: P) -> Result<S, Box<dyn Error>>
+/// Builds the network from the shapefile at `input` and the wegkenmerken of `options` and writes it
+/// to `output`, an existing network is overwritten.
+pub fn build_network<P>(
+    input: P,
+    output: P,
+    options: &BuildOptions,
+) -> Result<LiteNetwork, Box<dyn Error>>
 where
     P: AsRef<Path>,
-    S: Writeable + Network,
 {
-    if File::open(output.as_ref()).is_ok() {
-        println!("Output exists, already preprocessed");
-        return S::read(output.as_ref());
+    let source = source(input.as_ref(), options)?;
+    let attributes = options.read_attributes()?;
+    let mut network = nwb::read(
+        input,
+        &options.speeds,
+        &attributes,
+        LiteNetworkBuilder::new(),
+    )?;
+    if let Some(order) = options.reorder {
+        network = network.reordered(order).0;
     }
 
-    let shapes = read_shapes(input)?;
-
-    println!("No output exists, creating preprocessed");
-    let writeable: S = shapes.into();
-    writeable.write(output)?;
-    Ok(writeable)
+    file::write_network(&network, output, options, &source)?;
+    Ok(network)
 }
 
-fn read_shapes<P: AsRef<Path>>(
-    path: P,
-) -> Result<ShapeRecordIterator<BufReader<File>, Polyline>, shapefile::Error> {
-    shapefile::Reader::from_path(path)
-        .and_then(|reader| reader.iter_shapes_and_records_as::<Polyline>())
+/// The stamps and the FNV-1a hash of the contents of the source files of a network built from
+/// `shapefile` with `options`.
+fn source(shapefile: &Path, options: &BuildOptions) -> Result<Source, Box<dyn Error>> {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut buffer = vec![0; 1 << 16];
+    let mut files = Vec::new();
+
+    for path in options.source_files(shapefile) {
+        files.push(FileStamp::new(&path)?);
+        let mut reader = File::open(&path).map_err(|error| format!("{:?}: {}", path, error))?;
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            for byte in &buffer[..read] {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+    }
+
+    Ok(Source { hash, files })
 }
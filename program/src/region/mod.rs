@@ -0,0 +1,382 @@
+// Region based loading of the overlay graphs of highway node routing.
+//
+// Every level has a grid of square cells, the cells get larger with every level. A node is put in
+// the cell around its location on the highest level it is a highway node of, together with the
+// overlay edges of that level it relaxes in a query. A search starts in the level 0 cells around
+// the start points and only loads the cells of higher levels when it reaches their highway nodes,
+// so the cells that are loaded depend on the query and not on the size of the network.
+//
+// The nodes and edges of a cell get consecutive ids, the id is enough to find the cell. An edge is
+// stored in the cells of both its nodes when the query relaxes it from both sides.
+//
+// The header and every cell are written to their own file, which starts with `MAGIC`,
+// `REGION_VERSION` and a `FileLabel` saying which regions and which cell it belongs to. Opening the
+// regions only reads the header, a cell file is read and checked against its label and the header
+// the first time the cell is used. A missing or changed cell is an error of the query that needs
+// it.
+//
+// The regions are only used as a library, the command line does not write or query them.
+pub mod network;
+
+pub use network::RegionNetwork;
+
+use crate::{
+    algorithm::dijkstra::DijkstraDirection,
+    network::{network_lite::Adjacency, EdgeId, Network, NodeCoord, NodeId},
+    node_routing::MultiLevelOverlay,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const HEADER_FILE: &str = "header.axe";
+
+const MAGIC: [u8; 8] = *b"DUTCHRG\0";
+
+/// Increase when the serialized layout of the header or of a cell changes.
+pub const REGION_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileLabel {
+    // The same for all files written by one `write_regions`.
+    regions: u64,
+    // `None` for the header.
+    cell: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RegionParameters {
+    // Width of the cells on level 0 in meters.
+    pub cell_size: f32,
+    // Every level has cells this many times wider than the level below.
+    pub growth: f32,
+}
+
+impl Default for RegionParameters {
+    fn default() -> Self {
+        Self {
+            cell_size: 5000.0,
+            growth: 4.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RegionHeader {
+    // Label of the files of these regions.
+    regions: u64,
+    origin: NodeCoord,
+    // Cell width of every level.
+    cell_sizes: Vec<f32>,
+    // Sorted by (level, x, y).
+    cells: Vec<CellKey>,
+    // The nodes of cell i are `node_offsets[i]..node_offsets[i + 1]`, the same for edges.
+    node_offsets: Vec<usize>,
+    edge_offsets: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct CellKey {
+    level: u8,
+    x: i32,
+    y: i32,
+}
+
+impl RegionHeader {
+    fn cell_key(&self, level: u8, location: NodeCoord) -> CellKey {
+        let size = self.cell_sizes[level as usize];
+        CellKey {
+            level,
+            x: ((location.x - self.origin.x) / size).floor() as i32,
+            y: ((location.y - self.origin.y) / size).floor() as i32,
+        }
+    }
+
+    /// Checks that the offsets give every cell its nodes and edges.
+    fn check(&self) -> Result<(), String> {
+        for offsets in [&self.node_offsets, &self.edge_offsets] {
+            if offsets.len() != self.cells.len() + 1
+                || offsets.first() != Some(&0)
+                || offsets.windows(2).any(|pair| pair[0] > pair[1])
+            {
+                return Err("the offsets of the cells do not match the cells".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn cell_index(&self, key: CellKey) -> Option<usize> {
+        self.cells.binary_search(&key).ok()
+    }
+
+    /// Label of the file of cell `cell`, or of the header.
+    fn label(&self, cell: Option<usize>) -> FileLabel {
+        FileLabel {
+            regions: self.regions,
+            cell,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cell {
+    // Node i of the cell, edges are referred to by their region id.
+    original_node: Vec<NodeId>,
+    junction_id: Vec<u32>,
+    location: Vec<NodeCoord>,
+    outgoing_edges: Adjacency,
+    incoming_edges: Adjacency,
+    // Edge i of the cell, nodes are referred to by their region id.
+    source: Vec<NodeId>,
+    target: Vec<NodeId>,
+    // Decimetres.
+    distance: Vec<u32>,
+    speed: Vec<u8>,
+    lanes: Vec<u8>,
+    object_id: Vec<u32>,
+    // The edges of the original network behind edge i are
+    // `original_edges[original_offsets[i]..original_offsets[i + 1]]`.
+    original_offsets: Vec<usize>,
+    original_edges: Vec<EdgeId>,
+}
+
+impl Cell {
+    /// Checks that the cell has the nodes and edges the header gives it, that its adjacency only
+    /// has its own edges, and that its edges connect nodes of the regions.
+    fn check(
+        &self,
+        nodes: Range<usize>,
+        edges: Range<usize>,
+        all_nodes: usize,
+    ) -> Result<(), String> {
+        let node_lens = [
+            self.original_node.len(),
+            self.junction_id.len(),
+            self.location.len(),
+            self.outgoing_edges.nodes_len(),
+            self.incoming_edges.nodes_len(),
+        ];
+        let edge_lens = [
+            self.source.len(),
+            self.target.len(),
+            self.distance.len(),
+            self.speed.len(),
+            self.lanes.len(),
+            self.object_id.len(),
+        ];
+        if node_lens.iter().any(|len| *len != nodes.len())
+            || edge_lens.iter().any(|len| *len != edges.len())
+            || self.original_offsets.len() != edges.len() + 1
+        {
+            return Err(format!(
+                "does not have the {} nodes and {} edges of the cell",
+                nodes.len(),
+                edges.len()
+            ));
+        }
+
+        let own_edges = |adjacency: &Adjacency| {
+            (0..nodes.len())
+                .flat_map(|node| adjacency.get(NodeId::new(node)))
+                .all(|edge| edges.contains(&edge.index()))
+        };
+        if !own_edges(&self.outgoing_edges) || !own_edges(&self.incoming_edges) {
+            return Err("has edges of other cells".to_string());
+        }
+        if self
+            .source
+            .iter()
+            .chain(&self.target)
+            .any(|node| node.index() >= all_nodes)
+        {
+            return Err(format!("has edges to nodes beyond the {} nodes", all_nodes));
+        }
+
+        let offsets = &self.original_offsets;
+        if offsets.first() != Some(&0)
+            || offsets.windows(2).any(|pair| pair[0] > pair[1])
+            || offsets.last() != Some(&self.original_edges.len())
+        {
+            return Err("has original edges outside their array".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn cell_path(directory: &Path, index: usize) -> PathBuf {
+    directory.join(format!("cell_{}.axe", index))
+}
+
+fn write_file<T: Serialize>(
+    path: &Path,
+    label: FileLabel,
+    value: &T,
+) -> Result<(), Box<dyn Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&REGION_VERSION.to_le_bytes())?;
+    bincode::serialize_into(&mut writer, &label)?;
+    bincode::serialize_into(&mut writer, value)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Opens a file written by `write_file` and reads its label, the reader is left at the value.
+fn open_file(path: &Path) -> Result<(FileLabel, BufReader<File>), Box<dyn Error>> {
+    let file = File::open(path).map_err(|error| format!("{:?}: {}", path, error))?;
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0; 8];
+    if reader.read_exact(&mut magic).is_err() || magic != MAGIC {
+        return Err(format!("{:?} is not a region file", path).into());
+    }
+
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != REGION_VERSION {
+        return Err(format!(
+            "{:?} has region version {}, expected {}",
+            path, version, REGION_VERSION
+        )
+        .into());
+    }
+
+    let label = bincode::deserialize_from(&mut reader)?;
+    Ok((label, reader))
+}
+
+fn check_label(path: &Path, found: FileLabel, expected: FileLabel) -> Result<(), Box<dyn Error>> {
+    if found != expected {
+        return Err(format!("{:?} belongs to other regions or is another cell", path).into());
+    }
+    Ok(())
+}
+
+/// Reads the value of a file written by `write_file` with `label`.
+fn read_file<T: DeserializeOwned>(path: &Path, label: FileLabel) -> Result<T, Box<dyn Error>> {
+    let (found, reader) = open_file(path)?;
+    check_label(path, found, label)?;
+    Ok(bincode::deserialize_from(reader)?)
+}
+
+/// Splits the overlay graphs into cells and writes them to `directory`, which can then be opened
+/// as a `RegionNetwork`.
+pub fn write_regions<P: AsRef<Path>>(
+    overlay: &MultiLevelOverlay,
+    parameters: RegionParameters,
+    directory: P,
+) -> Result<(), Box<dyn Error>> {
+    let directory = directory.as_ref();
+    let network = overlay.network();
+
+    let locations = (0..network.nodes_len())
+        .map(|node| network.node_location(NodeId::new(node)))
+        .collect::<Vec<_>>();
+    let origin = NodeCoord {
+        x: locations.iter().map(|l| l.x).fold(f32::INFINITY, f32::min),
+        y: locations.iter().map(|l| l.y).fold(f32::INFINITY, f32::min),
+    };
+
+    let mut header = RegionHeader {
+        regions: SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+        origin,
+        cell_sizes: (0..=overlay.top_level())
+            .map(|level| parameters.cell_size * parameters.growth.powi(level as i32))
+            .collect(),
+        cells: Vec::new(),
+        node_offsets: vec![0],
+        edge_offsets: vec![0],
+    };
+
+    let mut nodes = (0..network.nodes_len())
+        .map(|node| {
+            let node = NodeId::new(node);
+            (
+                header.cell_key(overlay.node_level(node), locations[node]),
+                node,
+            )
+        })
+        .collect::<Vec<_>>();
+    nodes.sort();
+
+    let mut region_node = vec![NodeId(0); network.nodes_len()];
+    for (index, (key, node)) in nodes.iter().enumerate() {
+        region_node[*node] = NodeId::new(index);
+        if header.cells.last() != Some(key) {
+            if !header.cells.is_empty() {
+                header.node_offsets.push(index);
+            }
+            header.cells.push(*key);
+        }
+    }
+    header.node_offsets.push(nodes.len());
+
+    fs::create_dir_all(directory)?;
+
+    for (index, key) in header.cells.iter().enumerate() {
+        let cell_nodes = &nodes[header.node_offsets[index]..header.node_offsets[index + 1]];
+        let first_edge = *header.edge_offsets.last().unwrap();
+
+        let mut cell = Cell {
+            original_offsets: vec![0],
+            ..Cell::default()
+        };
+        // Overlay edge -> region edge, an edge is stored once per cell.
+        let mut cell_edges: HashMap<EdgeId, EdgeId> = HashMap::new();
+        let mut region_edge = |cell: &mut Cell, edge: EdgeId| {
+            *cell_edges.entry(edge).or_insert_with(|| {
+                cell.source.push(region_node[network.edge_source(edge)]);
+                cell.target.push(region_node[network.edge_target(edge)]);
+                cell.distance.push(network.edge_decimetres(edge));
+                cell.speed.push(network.edge_speed(edge));
+                cell.lanes.push(network.edge_lanes(edge));
+                cell.object_id.push(network.edge_object_id(edge));
+                overlay
+                    .shortcuts()
+                    .unpack_edge(edge, &mut cell.original_edges);
+                cell.original_offsets.push(cell.original_edges.len());
+                EdgeId::new(first_edge + cell.source.len() - 1)
+            })
+        };
+
+        for (row, (_, node)) in cell_nodes.iter().enumerate() {
+            let row = NodeId::new(row);
+            cell.outgoing_edges.add_node();
+            cell.incoming_edges.add_node();
+            for (_, edge) in overlay.level_neighbours(*node, key.level, &DijkstraDirection::Forward)
+            {
+                let edge = region_edge(&mut cell, edge);
+                cell.outgoing_edges.push(row, edge);
+            }
+            for (_, edge) in
+                overlay.level_neighbours(*node, key.level, &DijkstraDirection::Backward)
+            {
+                let edge = region_edge(&mut cell, edge);
+                cell.incoming_edges.push(row, edge);
+            }
+
+            cell.original_node.push(*node);
+            cell.junction_id.push(network.junction_id(*node));
+            cell.location.push(locations[*node]);
+        }
+        cell.outgoing_edges.compact();
+        cell.incoming_edges.compact();
+
+        header.edge_offsets.push(first_edge + cell.source.len());
+        write_file(
+            &cell_path(directory, index),
+            header.label(Some(index)),
+            &cell,
+        )?;
+    }
+
+    write_file(&directory.join(HEADER_FILE), header.label(None), &header)
+}
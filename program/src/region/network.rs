@@ -0,0 +1,213 @@
+use super::{cell_path, check_label, open_file, read_file, Cell, RegionHeader, HEADER_FILE};
+use crate::network::{EdgeId, Network, NodeCoord, NodeId, DECIMETRES_PER_METRE};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// The overlay graphs written by `write_regions`, cells are read from disk the first time one of
+/// their nodes or edges is used.
+///
+/// Every node only has the edges of its own level, so a bidirectional search on this network is
+/// a highway node routing query. Edges are not unpacked, use `original_edges` for that.
+///
+/// A cell file is checked when it is read. When a cell can not be read, because its file was
+/// removed, damaged or written for other regions, its nodes have no edges and `check_loaded_cells`
+/// returns why. `RegionPathAlgorithm` fails the query then.
+#[derive(Debug)]
+pub struct RegionNetwork {
+    directory: PathBuf,
+    header: RegionHeader,
+    // The cell, or why it could not be read.
+    cells: Vec<OnceLock<Result<Cell, String>>>,
+}
+
+impl RegionNetwork {
+    /// Reads the header of the regions in `directory`, no cells are read yet.
+    pub fn open<P: AsRef<Path>>(directory: P) -> Result<Self, Box<dyn Error>> {
+        let directory = directory.as_ref().to_path_buf();
+        let path = directory.join(HEADER_FILE);
+        let (label, reader) = open_file(&path)?;
+        let header: RegionHeader = bincode::deserialize_from(reader)?;
+        check_label(&path, label, header.label(None))?;
+        header
+            .check()
+            .map_err(|error| format!("{:?}: {}", path, error))?;
+
+        let cells = header.cells.iter().map(|_| OnceLock::new()).collect();
+
+        Ok(Self {
+            directory,
+            header,
+            cells,
+        })
+    }
+
+    pub fn cells_len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn loaded_cells(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|cell| matches!(cell.get(), Some(Ok(_))))
+            .count()
+    }
+
+    /// Why the first cell that was used but could not be read failed, `Ok` when all cells used so
+    /// far were read.
+    pub fn check_loaded_cells(&self) -> Result<(), Box<dyn Error>> {
+        match self
+            .cells
+            .iter()
+            .find_map(|cell| cell.get()?.as_ref().err())
+        {
+            Some(error) => Err(error.as_str().into()),
+            None => Ok(()),
+        }
+    }
+
+    /// The node of the original network, only the cells around `location` are loaded to find it.
+    pub fn find_node(
+        &self,
+        original: NodeId,
+        location: NodeCoord,
+    ) -> Result<Option<NodeId>, Box<dyn Error>> {
+        for level in 0..self.header.cell_sizes.len() as u8 {
+            let key = self.header.cell_key(level, location);
+            let index = match self.header.cell_index(key) {
+                Some(index) => index,
+                None => continue,
+            };
+            let position = self
+                .cell(index)?
+                .original_node
+                .iter()
+                .position(|node| *node == original);
+            if let Some(position) = position {
+                return Ok(Some(NodeId::new(
+                    self.header.node_offsets[index] + position,
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The edges of the original network behind an edge.
+    pub fn original_edges(&self, edge: EdgeId) -> Result<&[EdgeId], Box<dyn Error>> {
+        let (index, edge) = cell_of(&self.header.edge_offsets, edge.index());
+        let cell = self.cell(index)?;
+        Ok(&cell.original_edges[cell.original_offsets[edge]..cell.original_offsets[edge + 1]])
+    }
+
+    /// Cell `index`, which is read when it is first used.
+    fn cell(&self, index: usize) -> Result<&Cell, Box<dyn Error>> {
+        self.cells[index]
+            .get_or_init(|| self.read_cell(index).map_err(|error| error.to_string()))
+            .as_ref()
+            .map_err(|error| error.as_str().into())
+    }
+
+    fn read_cell(&self, index: usize) -> Result<Cell, Box<dyn Error>> {
+        let path = cell_path(&self.directory, index);
+        let cell: Cell = read_file(&path, self.header.label(Some(index)))
+            .map_err(|error| format!("region cell {}: {}", index, error))?;
+        let range = |offsets: &[usize]| offsets[index]..offsets[index + 1];
+        cell.check(
+            range(&self.header.node_offsets),
+            range(&self.header.edge_offsets),
+            self.nodes_len(),
+        )
+        .map_err(|error| format!("region cell {} in {:?} {}", index, path, error))?;
+        Ok(cell)
+    }
+
+    /// The cell of a node and the index of the node in the cell, `None` when the cell can not be
+    /// read.
+    fn node_cell(&self, node: NodeId) -> Option<(&Cell, usize)> {
+        let (index, node) = cell_of(&self.header.node_offsets, node.index());
+        Some((self.cell(index).ok()?, node))
+    }
+
+    /// The cell of an edge and the index of the edge in the cell, `None` when the cell can not be
+    /// read.
+    fn edge_cell(&self, edge: EdgeId) -> Option<(&Cell, usize)> {
+        let (index, edge) = cell_of(&self.header.edge_offsets, edge.index());
+        Some((self.cell(index).ok()?, edge))
+    }
+}
+
+/// The cell whose range of `offsets` has `id`, and the index of `id` in that cell.
+fn cell_of(offsets: &[usize], id: usize) -> (usize, usize) {
+    let index = offsets.partition_point(|offset| *offset <= id) - 1;
+    (index, id - offsets[index])
+}
+
+// The nodes of a cell that can not be read have no edges, so a search never reaches its edges.
+// Their other values are zero, and their location is the origin of the regions.
+impl Network for RegionNetwork {
+    fn nodes_len(&self) -> usize {
+        *self.header.node_offsets.last().unwrap()
+    }
+
+    fn junction_id(&self, id: NodeId) -> u32 {
+        self.node_cell(id)
+            .map_or(0, |(cell, node)| cell.junction_id[node])
+    }
+
+    fn outgoing_edges(&self, id: NodeId) -> &[EdgeId] {
+        self.node_cell(id).map_or(&[], |(cell, node)| {
+            cell.outgoing_edges.get(NodeId::new(node))
+        })
+    }
+
+    fn incoming_edges(&self, id: NodeId) -> &[EdgeId] {
+        self.node_cell(id).map_or(&[], |(cell, node)| {
+            cell.incoming_edges.get(NodeId::new(node))
+        })
+    }
+
+    fn node_location(&self, id: NodeId) -> NodeCoord {
+        self.node_cell(id)
+            .map_or(self.header.origin, |(cell, node)| cell.location[node])
+    }
+
+    fn edge_len(&self) -> usize {
+        *self.header.edge_offsets.last().unwrap()
+    }
+
+    fn edge_source(&self, id: EdgeId) -> NodeId {
+        self.edge_cell(id)
+            .map_or(NodeId(0), |(cell, edge)| cell.source[edge])
+    }
+
+    fn edge_target(&self, id: EdgeId) -> NodeId {
+        self.edge_cell(id)
+            .map_or(NodeId(0), |(cell, edge)| cell.target[edge])
+    }
+
+    fn edge_object_id(&self, id: EdgeId) -> u32 {
+        self.edge_cell(id)
+            .map_or(0, |(cell, edge)| cell.object_id[edge])
+    }
+
+    fn edge_distance(&self, id: EdgeId) -> f32 {
+        self.edge_decimetres(id) as f32 / DECIMETRES_PER_METRE
+    }
+
+    fn edge_decimetres(&self, id: EdgeId) -> u32 {
+        self.edge_cell(id)
+            .map_or(0, |(cell, edge)| cell.distance[edge])
+    }
+
+    fn edge_speed(&self, id: EdgeId) -> u8 {
+        self.edge_cell(id)
+            .map_or(0, |(cell, edge)| cell.speed[edge])
+    }
+
+    fn edge_lanes(&self, id: EdgeId) -> u8 {
+        self.edge_cell(id)
+            .map_or(0, |(cell, edge)| cell.lanes[edge])
+    }
+}